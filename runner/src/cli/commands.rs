@@ -1,19 +1,56 @@
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use dialoguer::MultiSelect;
 use log::{debug, error, info, warn};
 use std::fs;
-use std::path::PathBuf;
-
-use crate::cli::ListFormat;
-use crate::scripts::display::list_scripts;
-use crate::scripts::fs::create_temp_dir;
-use crate::scripts::models::PathNames;
-use crate::scripts::runner::run_scripts;
-
-pub fn interactive_mode(scripts: &[PathBuf], all: bool) -> Result<()> {
-    // Assume clean install, so run all scripts by default if all flag is set
-    let default_selections: Vec<bool> = vec![all; scripts.len()];
-    let script_names: Vec<String> = scripts.to_vec().into_names();
+use std::num::NonZeroUsize;
+use std::time::Duration;
+
+use crate::cli::{ListFormat, RunOptions};
+use crate::config::RunnerConfig;
+use crate::scripts::action::Action;
+use crate::scripts::display::{
+    list_scripts, print_doctor_report, print_dry_run, print_execution_plan, print_inventory,
+    print_run_summary, print_status,
+};
+use crate::scripts::doctor::examine;
+use crate::scripts::exports::Exports;
+use crate::scripts::fs::{create_temp_dir, temp_dir_path};
+use crate::scripts::inventory::take_inventory;
+use crate::scripts::logs::{
+    find_transcript, list_logged_runs, list_transcripts, prune_logs, run_log_dir, RunLogs,
+    COMBINED_LOG,
+};
+use crate::scripts::models::ScriptInfo;
+use crate::scripts::outcome::{RunReport, ScriptStatus};
+use crate::scripts::plan::{
+    find_dependents, resolve_execution_plan, resolve_removal_plan, ExecutionPlan,
+};
+use crate::scripts::process::install_interrupt_handler;
+use crate::scripts::runner::{run_scripts, ExecutionOptions};
+use crate::scripts::runs::{RunRecord, RunSettings};
+use crate::scripts::selector::{resolve_available_selectors, resolve_selectors};
+use crate::scripts::shellenv::{Shell, ShellEnvStore};
+use crate::scripts::state::StateStore;
+
+pub fn interactive_mode(
+    scripts: &[ScriptInfo],
+    all: bool,
+    mut options: RunOptions,
+    config: &RunnerConfig,
+) -> Result<()> {
+    let preselected = apply_profile(scripts, &mut options, config)?;
+    // Assume clean install, so run all scripts by default if all flag is set,
+    // otherwise start from the profile's scripts
+    let default_selections: Vec<bool> = (0..scripts.len())
+        .map(|index| all || preselected.contains(&index))
+        .collect();
+    let script_names: Vec<String> = scripts
+        .iter()
+        .map(|script| match &script.metadata.description {
+            Some(description) => format!("{} - {}", script.display_name(), description),
+            None => script.display_name(),
+        })
+        .collect();
     debug!("Showing script selection dialog");
     let selections = MultiSelect::new()
         .with_prompt("Select scripts to run (space to toggle, enter to confirm)")
@@ -27,67 +64,654 @@ pub fn interactive_mode(scripts: &[PathBuf], all: bool) -> Result<()> {
     }
 
     info!("Selected {} scripts to run", selections.len());
-    execute_scripts(scripts, &selections)
+    execute_scripts(scripts, &selections, &options, config)
 }
 
-pub fn run_specified_scripts(scripts: &[PathBuf], script_names: Vec<String>) -> Result<()> {
-    let mut selections = Vec::new();
-    let all_script_names = scripts.to_vec().into_names();
+/// Runs the scripts matching `selectors`, plus those of the profile in
+/// `options` if one is given.
+pub fn run_specified_scripts(
+    scripts: &[ScriptInfo],
+    selectors: Vec<String>,
+    mut options: RunOptions,
+    config: &RunnerConfig,
+) -> Result<()> {
+    let selections = select_scripts(scripts, &selectors, &mut options, config)?;
+    if selections.is_empty() {
+        warn!("No matching scripts found.");
+        return Ok(());
+    }
+
+    info!("Running {} specified scripts", selections.len());
+    execute_scripts(scripts, &selections, &options, config)
+}
+
+/// Runs the remove actions of the scripts matching `selectors` (and those of
+/// the profile in `options`), dependents before the scripts they require.
+///
+/// Fails if a script has no remove action, or if installed scripts outside
+/// the selection require one of them, unless `ignore_dependents` is set.
+pub fn remove_mode(
+    scripts: &[ScriptInfo],
+    selectors: Vec<String>,
+    ignore_dependents: bool,
+    mut options: RunOptions,
+    config: &RunnerConfig,
+) -> Result<()> {
+    options.action = Action::Remove;
+    let selections = select_scripts(scripts, &selectors, &mut options, config)?;
+    if selections.is_empty() {
+        warn!("No matching scripts found.");
+        return Ok(());
+    }
 
-    for name in &script_names {
-        if let Some(pos) = all_script_names.iter().position(|n| n == name) {
-            selections.push(pos);
+    let unsupported: Vec<String> = selections
+        .iter()
+        .filter(|&&index| Action::Remove.resolve(&scripts[index]).is_none())
+        .map(|&index| scripts[index].display_name())
+        .collect();
+    if !unsupported.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No remove action for {}; add a `<name>.remove.sh` companion script or declare `@actions: remove`",
+            unsupported.join(", ")
+        ));
+    }
+
+    let store = StateStore::open_default()?;
+    let mut blocked = Vec::new();
+    for &index in &selections {
+        let dependents: Vec<String> = find_dependents(scripts, index)
+            .into_iter()
+            .filter(|dependent| !selections.contains(dependent))
+            .filter(|&dependent| store.is_installed(&scripts[dependent]))
+            .map(|dependent| scripts[dependent].display_name())
+            .collect();
+        if dependents.is_empty() {
+            continue;
+        }
+        let message = format!(
+            "{} is required by installed {}",
+            scripts[index].display_name(),
+            dependents.join(", ")
+        );
+        if ignore_dependents {
+            warn!("{}", message);
         } else {
-            warn!("Script not found: {}", name);
+            blocked.push(message);
         }
     }
+    if !blocked.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Refusing to remove scripts that installed scripts depend on:\n  {}\nRemove the dependents too, or pass --ignore-dependents",
+            blocked.join("\n  ")
+        ));
+    }
 
+    info!("Removing {} scripts", selections.len());
+    execute_scripts(scripts, &selections, &options, config)
+}
+
+/// Runs the upgrade actions of the installed scripts matching `selectors`
+/// (and those of the profile in `options`), or of every installed script if
+/// nothing is selected. Scripts without an upgrade action are installed again.
+pub fn upgrade_mode(
+    scripts: &[ScriptInfo],
+    selectors: Vec<String>,
+    mut options: RunOptions,
+    config: &RunnerConfig,
+) -> Result<()> {
+    options.action = Action::Upgrade;
+    let everything = selectors.is_empty() && options.profile.is_none();
+    let selections = if everything {
+        (0..scripts.len()).collect()
+    } else {
+        select_scripts(scripts, &selectors, &mut options, config)?
+    };
+    options.jobs = options.jobs.or(Some(config.jobs.value));
+
+    let store = StateStore::open_default()?;
+    let selections: Vec<usize> = selections
+        .into_iter()
+        .filter(|&index| {
+            let installed = store.is_installed(&scripts[index]);
+            if !installed && !everything {
+                warn!("Skipping {}: not installed", scripts[index].display_name());
+            }
+            installed
+        })
+        .collect();
     if selections.is_empty() {
-        warn!("No matching scripts found.");
+        warn!("No installed scripts to upgrade.");
         return Ok(());
     }
 
-    info!("Running {} specified scripts", selections.len());
-    execute_scripts(scripts, &selections)
+    let reinstalled: Vec<String> = selections
+        .iter()
+        .filter(|&&index| {
+            Action::Upgrade
+                .resolve(&scripts[index])
+                .is_some_and(|(action, _)| action.is_install())
+        })
+        .map(|&index| scripts[index].display_name())
+        .collect();
+    if !reinstalled.is_empty() {
+        info!(
+            "No upgrade action, installing again: {}",
+            reinstalled.join(", ")
+        );
+    }
+
+    info!("Upgrading {} scripts", selections.len());
+    execute_scripts(scripts, &selections, &options, config)
+}
+
+/// Resolves `selectors` plus the scripts of the profile in `options`.
+fn select_scripts(
+    scripts: &[ScriptInfo],
+    selectors: &[String],
+    options: &mut RunOptions,
+    config: &RunnerConfig,
+) -> Result<Vec<usize>> {
+    let mut selections = apply_profile(scripts, options, config)?;
+    for index in resolve_selectors(scripts, selectors)? {
+        if !selections.contains(&index) {
+            selections.push(index);
+        }
+    }
+    Ok(selections)
 }
 
-pub fn list_mode(scripts: &[PathBuf], format: ListFormat) -> Result<()> {
+/// Applies the profile named in `options`, if any, and then the configured
+/// defaults, returning the indices of the scripts the profile selects.
+fn apply_profile(
+    scripts: &[ScriptInfo],
+    options: &mut RunOptions,
+    config: &RunnerConfig,
+) -> Result<Vec<usize>> {
+    let mut selections = Vec::new();
+    if let Some(name) = &options.profile {
+        let profile = config.profile(name)?;
+        info!("Using profile '{}'", profile.name);
+        options.apply_profile(&profile);
+        selections = resolve_available_selectors(scripts, &profile.scripts)?;
+    }
+    options.jobs = options.jobs.or(Some(config.jobs.value));
+    Ok(selections)
+}
+
+/// Prints the resolved execution order without running anything.
+/// With no selectors, plans every available script.
+pub fn plan_mode(scripts: &[ScriptInfo], selectors: Vec<String>) -> Result<()> {
+    let selections = if selectors.is_empty() {
+        (0..scripts.len()).collect()
+    } else {
+        resolve_selectors(scripts, &selectors)?
+    };
+
+    let plan = resolve_execution_plan(scripts, &selections)?;
+    print_execution_plan(scripts, &plan);
+    Ok(())
+}
+
+pub fn list_mode(scripts: &[ScriptInfo], format: ListFormat, config: &RunnerConfig) -> Result<()> {
     info!("Listing available scripts");
-    let script_infos = scripts.to_vec().into_script_infos();
 
-    match format {
-        ListFormat::Plain => list_scripts(&script_infos, "plain")?,
-        ListFormat::Json => list_scripts(&script_infos, "json")?,
-        ListFormat::Csv => list_scripts(&script_infos, "csv")?,
-        ListFormat::Table => list_scripts(&script_infos, "table")?,
+    list_scripts(scripts, format.name(), &config.scripts_dirs.value)
+}
+
+/// Reports the versions of the commands and packages declared by the scripts
+/// matching `selectors`, or by every script if nothing is selected.
+pub fn inventory_mode(
+    scripts: &[ScriptInfo],
+    selectors: Vec<String>,
+    format: ListFormat,
+) -> Result<()> {
+    let selections: Vec<usize> = if selectors.is_empty() {
+        (0..scripts.len()).collect()
+    } else {
+        resolve_selectors(scripts, &selectors)?
+    };
+    let selections: Vec<usize> = selections
+        .into_iter()
+        .filter(|&index| {
+            let metadata = &scripts[index].metadata;
+            !metadata.provides.is_empty() || !metadata.packages.is_empty()
+        })
+        .collect();
+    if selections.is_empty() {
+        warn!("No selected scripts declare `@provides` or `@packages`.");
+        return Ok(());
+    }
+
+    info!("Taking inventory of {} scripts", selections.len());
+    install_interrupt_handler();
+    let shell_paths = ShellEnvStore::load()?.paths();
+    let inventory = take_inventory(scripts, &selections, &shell_paths);
+    print_inventory(&inventory, format.name())
+}
+
+/// Shows when each script was last applied and whether it changed since.
+pub fn status_mode(scripts: &[ScriptInfo]) -> Result<()> {
+    let store = StateStore::open_default()?;
+    debug!("Loaded state from {}", store.path().display());
+    print_status(scripts, &store)
+}
+
+/// Checks the commands declared in `@provides` by the scripts matching
+/// `selectors`, by every installed script if nothing is selected, or by
+/// every script with `all`. Fails if any command is missing or outdated.
+pub fn doctor_mode(
+    scripts: &[ScriptInfo],
+    selectors: Vec<String>,
+    all: bool,
+    no_version: bool,
+) -> Result<()> {
+    let store = StateStore::open_default()?;
+    let selections: Vec<usize> = if all {
+        (0..scripts.len()).collect()
+    } else if selectors.is_empty() {
+        (0..scripts.len())
+            .filter(|&index| store.is_installed(&scripts[index]))
+            .collect()
+    } else {
+        resolve_selectors(scripts, &selectors)?
+    };
+    let selections: Vec<usize> = selections
+        .into_iter()
+        .filter(|&index| !scripts[index].metadata.provides.is_empty())
+        .collect();
+    if selections.is_empty() {
+        if selectors.is_empty() && !all {
+            warn!("No installed scripts declare `@provides`; use --all to check every script.");
+        } else {
+            warn!("No selected scripts declare `@provides`.");
+        }
+        return Ok(());
     }
 
+    install_interrupt_handler();
+    let shell_paths = ShellEnvStore::load()?.paths();
+    let findings = examine(scripts, &selections, &shell_paths, !no_version);
+    print_doctor_report(scripts, &findings, &store);
+
+    let failed = findings
+        .iter()
+        .filter(|finding| !finding.health.is_ok())
+        .count();
+    if failed > 0 {
+        bail!(
+            "{} of {} provided commands need attention",
+            failed,
+            findings.len()
+        );
+    }
     Ok(())
 }
 
-fn execute_scripts(scripts: &[PathBuf], selections: &[usize]) -> Result<()> {
+/// Appends the executed scripts of `report` to the install-state database.
+/// Failing to persist state is reported but does not fail the run.
+fn record_run(scripts: &[ScriptInfo], report: &RunReport, action: Action) {
+    let result = StateStore::open_default().and_then(|mut store| {
+        for outcome in &report.outcomes {
+            store.record(&scripts[outcome.index], outcome, action);
+        }
+        store.save()?;
+        debug!("Recorded run in {}", store.path().display());
+        Ok(())
+    });
+    if let Err(e) = result {
+        warn!("Failed to record run state: {:#}", e);
+    }
+}
+
+/// Prints the effective configuration and where each value came from.
+pub fn config_show_mode(config: &RunnerConfig) -> Result<()> {
+    let entries = config.entries();
+    let key_width = entries.iter().map(|(key, ..)| key.len()).max().unwrap_or(0) + 2;
+    let value_width = entries
+        .iter()
+        .map(|(_, value, _)| value.len())
+        .chain(std::iter::once("Value".len()))
+        .max()
+        .unwrap_or(0)
+        + 2;
+
+    println!("{:<key_width$}{:<value_width$}Source", "Setting", "Value");
+    println!("{:-<width$}", "", width = key_width + value_width + 30);
+    for (key, value, source) in entries {
+        println!("{:<key_width$}{:<value_width$}{}", key, value, source);
+    }
+
+    println!();
+    println!("Config files:");
+    for (source, found) in &config.files {
+        let state = if *found { "loaded" } else { "not found" };
+        println!("  {:<10} {}", state, source);
+    }
+    let families = &config.os_families;
+    if !families.dirs.is_empty() || !families.fallbacks.is_empty() {
+        println!();
+        println!("OS directories:");
+        for (os_type, dir) in &families.dirs {
+            println!("  {} uses {}", os_type, dir);
+        }
+        for (dir, parent) in &families.fallbacks {
+            if parent.is_empty() {
+                println!("  {} has no fallback", dir);
+            } else {
+                println!("  {} falls back to {}", dir, parent);
+            }
+        }
+    }
+    if !config.profiles.is_empty() {
+        println!();
+        println!(
+            "Profiles: {}",
+            config
+                .profiles
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Prints the shell settings declared by scripts, for `shell` or the login
+/// shell, so they can be evaluated with e.g. `eval "$(runner shellenv)"`.
+pub fn shellenv_mode(shell: Option<Shell>) -> Result<()> {
+    let shell = shell.or_else(Shell::login).unwrap_or(Shell::Bash);
+    let store = ShellEnvStore::load()?;
+    if store.scripts.is_empty() {
+        info!("No script has declared shell settings yet");
+    }
+    print!("{}", store.render(shell));
+    Ok(())
+}
+
+/// Lists runs with logs, the transcripts of a run, or prints a transcript.
+pub fn logs_mode(
+    run_id: Option<String>,
+    script: Option<String>,
+    tail: Option<usize>,
+    config: &RunnerConfig,
+) -> Result<()> {
+    let root = &config.log_dir.value;
+    let Some(run_id) = run_id else {
+        let runs = list_logged_runs(root)?;
+        if runs.is_empty() {
+            info!("No run logs recorded yet");
+        }
+        for run_id in runs.iter().rev() {
+            let state = match RunRecord::load(run_id) {
+                Ok(run) if run.is_complete() => "complete",
+                Ok(_) => "incomplete",
+                Err(_) => "unknown",
+            };
+            println!("{}  {}", run_id, state);
+        }
+        return Ok(());
+    };
+
+    let Some(script) = script else {
+        let dir = run_log_dir(root, &run_id)?;
+        println!("{}", dir.join(COMBINED_LOG).display());
+        for transcript in list_transcripts(root, &run_id)? {
+            println!("{}", dir.join(transcript).display());
+        }
+        return Ok(());
+    };
+
+    let path = find_transcript(root, &run_id, &script)?;
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read log: {}", path.display()))?;
+    let lines: Vec<&str> = content.lines().collect();
+    let start = tail.map_or(0, |count| lines.len().saturating_sub(count));
+    for line in &lines[start..] {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// Continues a recorded run from where it stopped. Scripts that already
+/// succeeded or were satisfied are not run again; failed ones are retried
+/// unless `skip_failed`.
+pub fn resume_mode(
+    scripts: &[ScriptInfo],
+    run_id: Option<String>,
+    skip_failed: bool,
+    config: &RunnerConfig,
+) -> Result<()> {
+    let mut run = match run_id {
+        Some(id) => RunRecord::load(&id)?,
+        None => RunRecord::latest_incomplete()?
+            .ok_or_else(|| anyhow::anyhow!("No incomplete run to resume"))?,
+    };
+    if run.is_complete() {
+        info!("Run {} already completed successfully", run.id);
+        return Ok(());
+    }
+    info!("Resuming run {}", run.id);
+
+    let selections = run.resolve_selections(scripts)?;
+    let plan = resolve_plan(scripts, &selections, run.settings.action)?;
+    let mut done = run.indices_with_status(scripts, ScriptStatus::Ok)?;
+    done.extend(run.indices_with_status(scripts, ScriptStatus::Satisfied)?);
+    if skip_failed {
+        let failed = run.indices_with_status(scripts, ScriptStatus::Failed)?;
+        for &index in &failed {
+            warn!("Skipping failed script: {}", scripts[index].display_name());
+            run.mark_skipped(&scripts[index]);
+        }
+        // Scripts requiring a skipped script cannot run either.
+        let mut blocked = Vec::new();
+        for &index in &plan.order {
+            if done.contains(&index) || failed.contains(&index) {
+                continue;
+            }
+            if let Some(&blocker) = failed.iter().find(|&&failed| plan.requires(index, failed)) {
+                warn!(
+                    "Skipping {}: requires {}",
+                    scripts[index].display_name(),
+                    scripts[blocker].display_name()
+                );
+                run.mark_skipped(&scripts[index]);
+                blocked.push(index);
+            }
+        }
+        done.extend(failed);
+        done.extend(blocked);
+    }
+
+    let plan = plan.without(&done);
+    if plan.order.is_empty() {
+        info!("Nothing left to run for {}", run.id);
+        return run.save();
+    }
+
+    let settings = &run.settings;
+    let options = RunOptions {
+        profile: settings.profile.clone(),
+        jobs: NonZeroUsize::new(settings.jobs),
+        keep_going: settings.keep_going,
+        timeout: settings.timeout_secs.map(Duration::from_secs),
+        env: settings.environment.clone(),
+        force: settings.force,
+        keep_logs: settings.keep_logs,
+        action: settings.action,
+        ..RunOptions::default()
+    };
+    execute_plan(scripts, &plan, &options, config, run)
+}
+
+fn execute_scripts(
+    scripts: &[ScriptInfo],
+    selections: &[usize],
+    options: &RunOptions,
+    config: &RunnerConfig,
+) -> Result<()> {
+    let plan = resolve_plan(scripts, selections, options.action)?;
+    if !plan.auto_included.is_empty() {
+        info!(
+            "Including {} required scripts not in the selection",
+            plan.auto_included.len()
+        );
+    }
+
+    if options.dry_run {
+        let temp_dir = temp_dir_path(&config.temp_root.value);
+        print_dry_run(
+            scripts,
+            &plan,
+            &temp_dir,
+            &execution_options(options, config, None),
+        );
+        return Ok(());
+    }
+
+    let settings = RunSettings {
+        jobs: options.jobs().get(),
+        keep_going: options.keep_going,
+        timeout_secs: options.timeout.map(|timeout| timeout.as_secs()),
+        profile: options.profile.clone(),
+        environment: options.env.clone(),
+        force: options.force,
+        keep_logs: options.keep_logs,
+        action: options.action,
+    };
+    let run = RunRecord::new(scripts, selections, &plan, settings);
+    execute_plan(scripts, &plan, options, config, run)
+}
+
+/// The execution plan of `selections` for `action`.
+fn resolve_plan(
+    scripts: &[ScriptInfo],
+    selections: &[usize],
+    action: Action,
+) -> Result<ExecutionPlan> {
+    match action {
+        Action::Install => resolve_execution_plan(scripts, selections),
+        Action::Remove => resolve_removal_plan(scripts, selections),
+        // Only the selected scripts are upgraded, in dependency order.
+        Action::Upgrade => {
+            let plan = resolve_execution_plan(scripts, selections)?;
+            let required = plan.auto_included.clone();
+            Ok(plan.without(&required))
+        }
+    }
+}
+
+fn execution_options(
+    options: &RunOptions,
+    config: &RunnerConfig,
+    logs: Option<RunLogs>,
+) -> ExecutionOptions {
+    ExecutionOptions {
+        jobs: options.jobs().get(),
+        keep_going: options.keep_going,
+        logs,
+        timeout: options.timeout,
+        environment: options.env.clone(),
+        default_interpreter: config.default_interpreter.value.clone(),
+        exports: Exports::default(),
+        force: options.force,
+        action: options.action,
+    }
+}
+
+fn execute_plan(
+    scripts: &[ScriptInfo],
+    plan: &ExecutionPlan,
+    options: &RunOptions,
+    config: &RunnerConfig,
+    mut run: RunRecord,
+) -> Result<()> {
+    print_execution_plan(scripts, plan);
+    info!("Run ID: {}", run.id);
+    if let Err(e) = run.save() {
+        warn!(
+            "Failed to save run record, it will not be resumable: {:#}",
+            e
+        );
+    }
+
     // Create and navigate to a temporary directory for script execution
     // to isolate and remove artifacts downloaded or built by the scripts.
     debug!("Creating temporary directory");
-    let temp_dir = create_temp_dir()?;
+    let temp_dir = create_temp_dir(&config.temp_root.value)?;
     info!("Created temporary directory: {}", temp_dir.display());
 
+    // From here on Ctrl-C stops the running scripts and ends the run cleanly
+    // instead of killing the runner.
+    install_interrupt_handler();
     info!("Running selected scripts...");
-    if let Err(e) = run_scripts(scripts, selections, &temp_dir) {
-        error!("Failed to run scripts: {}", e);
-        fs::remove_dir_all(&temp_dir).ok(); // Try to clean up even if script fails
-        return Err(e);
+    let log_dir = &config.log_dir.value;
+    let logs = match RunLogs::create(log_dir, &run.id) {
+        Ok(logs) => {
+            info!("Logging script output to {}", logs.dir().display());
+            Some(logs)
+        }
+        Err(e) => {
+            warn!("Script output will not be logged: {:#}", e);
+            None
+        }
+    };
+    if let Err(e) = prune_logs(log_dir, options.keep_logs()) {
+        warn!("Failed to prune old run logs: {:#}", e);
     }
 
+    let mut execution_options = execution_options(options, config, logs);
+    execution_options.exports = run.exports.clone();
+    let mut shell_env = ShellEnvStore::load()
+        .map_err(|e| warn!("Shell settings of scripts will not be saved: {:#}", e))
+        .ok();
+    let report = run_scripts(
+        scripts,
+        plan,
+        &temp_dir,
+        &execution_options,
+        &mut |outcome| {
+            run.update(&scripts[outcome.index], outcome);
+            if let Err(e) = run.save() {
+                warn!("Failed to save run progress: {:#}", e);
+            }
+            if let (Some(store), Some(declarations)) = (&mut shell_env, &outcome.shell) {
+                let script = &scripts[outcome.index].name;
+                if let Err(e) = store.apply(script, declarations.clone()) {
+                    warn!("Failed to update shell settings of {}: {:#}", script, e);
+                }
+            }
+        },
+    );
+    print_run_summary(scripts, &report);
+    record_run(scripts, &report, options.action);
+
     debug!("Removing temporary directory");
-    fs::remove_dir_all(&temp_dir).map_err(|e| {
-        anyhow::anyhow!(
+    if let Err(e) = fs::remove_dir_all(&temp_dir) {
+        error!(
             "Failed to remove temporary directory {}: {}",
             temp_dir.display(),
             e
-        )
-    })?;
-    info!("Cleaned up temporary directory: {}", temp_dir.display());
+        );
+    } else {
+        info!("Cleaned up temporary directory: {}", temp_dir.display());
+    }
+
+    if report.was_cancelled() {
+        info!("Resume this run with: runner resume {}", run.id);
+        return Err(anyhow::anyhow!("Run was cancelled"));
+    }
+    if report.has_failures() {
+        info!(
+            "Resume this run with: runner resume {} [--skip-failed]",
+            run.id
+        );
+        return Err(anyhow::anyhow!(
+            "{} of {} scripts failed",
+            report.count(ScriptStatus::Failed),
+            report.outcomes.len()
+        ));
+    }
     info!("All selected scripts completed successfully");
 
     Ok(())
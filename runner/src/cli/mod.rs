@@ -1,14 +1,52 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
 use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::config::{CliOverrides, Profile};
+use crate::scripts::action::Action;
+use crate::scripts::logs::DEFAULT_KEPT_RUNS;
+use crate::scripts::metadata::parse_duration;
+use crate::scripts::shellenv::Shell;
 
 pub mod commands;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 pub struct Cli {
-    /// Path to the scripts directory
-    #[arg(short, long, default_value = "../scripts")]
-    pub scripts_dir: PathBuf,
+    /// Path to a scripts directory; repeat to overlay several roots, later
+    /// ones adding or shadowing scripts [default: ../scripts]
+    #[arg(short, long)]
+    pub scripts_dir: Vec<PathBuf>,
+
+    /// Repository config file (defaults to runner.toml in the current
+    /// directory or next to the scripts directory)
+    #[arg(short, long)]
+    pub config: Option<PathBuf>,
+
+    /// Directory holding the logs of each run
+    #[arg(long)]
+    pub log_dir: Option<PathBuf>,
+
+    /// Directory in which the temporary working directories of runs are created
+    #[arg(long)]
+    pub temp_root: Option<PathBuf>,
+
+    /// Collect scripts as if on this OS (a script directory such as `darwin`,
+    /// or an OS type such as `macos`); scripts are only executed with
+    /// --force-platform
+    #[arg(long)]
+    pub os: Option<String>,
+
+    /// Collect scripts as if on this CPU architecture (e.g. `arm64`); scripts
+    /// are only executed with --force-platform
+    #[arg(long)]
+    pub arch: Option<String>,
+
+    /// Execute scripts even though --os or --arch do not match this machine
+    #[arg(long, default_value_t = false)]
+    pub force_platform: bool,
 
     #[command(subcommand)]
     pub command: Option<Commands>,
@@ -21,13 +59,111 @@ pub enum Commands {
         /// Flag to control whether all options are selected by default
         #[arg(short, long, default_value_t = false)]
         all: bool,
+
+        #[command(flatten)]
+        options: RunOptions,
     },
 
     /// Run specific scripts by name without interactive selection
     Run {
-        /// List of script names to run
-        #[arg(required = true)]
+        /// Scripts to run: `nodejs`, `nodejs.sh`, `debian/nodejs`, globs like `docker-*`, or `tag:dev`
+        #[arg(required_unless_present = "profile")]
+        scripts: Vec<String>,
+
+        #[command(flatten)]
+        options: RunOptions,
+    },
+
+    /// Undo what scripts installed by running their remove actions, dependents first
+    Remove {
+        /// Scripts to remove, using the same selectors as `run`
+        #[arg(required_unless_present = "profile")]
+        scripts: Vec<String>,
+
+        /// Remove scripts even if installed scripts outside the selection require them
+        #[arg(long, default_value_t = false)]
+        ignore_dependents: bool,
+
+        #[command(flatten)]
+        options: RunOptions,
+    },
+
+    /// Upgrade installed scripts with their upgrade actions, installing again if they have none
+    Upgrade {
+        /// Scripts to upgrade, using the same selectors as `run` (all installed scripts if omitted)
+        scripts: Vec<String>,
+
+        #[command(flatten)]
+        options: RunOptions,
+    },
+
+    /// Show the resolved execution order, including required scripts
+    Plan {
+        /// Scripts to plan, using the same selectors as `run` (all scripts if omitted)
+        scripts: Vec<String>,
+    },
+
+    /// Resume a failed or interrupted run from where it stopped
+    Resume {
+        /// ID of the run to resume (defaults to the latest incomplete run)
+        run_id: Option<String>,
+
+        /// Skip the scripts that failed instead of retrying them
+        #[arg(long, default_value_t = false)]
+        skip_failed: bool,
+    },
+
+    /// List run logs or print a script's transcript from a run
+    Logs {
+        /// Run ID, or `latest` (lists runs with logs when omitted)
+        run_id: Option<String>,
+
+        /// Script name, stem, `os/name` or `combined` (lists transcripts when omitted)
+        script: Option<String>,
+
+        /// Only print the last N lines
+        #[arg(short = 'n', long)]
+        tail: Option<usize>,
+    },
+
+    /// Show when each script was last applied and whether it changed since
+    Status,
+
+    /// Check that the commands scripts declare in `@provides` are on the PATH in the required versions
+    Doctor {
+        /// Scripts to check, using the same selectors as `run` (all installed scripts if omitted)
+        scripts: Vec<String>,
+
+        /// Check every script declaring `@provides`, installed or not
+        #[arg(short, long, default_value_t = false, conflicts_with = "scripts")]
+        all: bool,
+
+        /// Only check that the commands exist, without running `--version`
+        #[arg(long, default_value_t = false)]
+        no_version: bool,
+    },
+
+    /// Report the versions of the tools scripts install on this machine, from `@provides` and package managers
+    Inventory {
+        /// Scripts to report on, using the same selectors as `run` (all scripts if omitted)
         scripts: Vec<String>,
+
+        /// Output format for the report
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: ListFormat,
+    },
+
+    /// Print the shell settings declared by scripts, e.g. `eval "$(runner shellenv)"`
+    Shellenv {
+        /// Shell to print the settings for [default: the login shell, or bash]
+        #[arg(long, value_enum)]
+        shell: Option<Shell>,
+    },
+
+    /// Inspect the effective configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
     },
 
     /// List available scripts
@@ -38,6 +174,99 @@ pub enum Commands {
     },
 }
 
+impl Commands {
+    /// Whether the command executes scripts, rather than only describing them.
+    pub fn executes_scripts(&self) -> bool {
+        match self {
+            Commands::Interactive { options, .. }
+            | Commands::Run { options, .. }
+            | Commands::Remove { options, .. }
+            | Commands::Upgrade { options, .. } => !options.dry_run,
+            Commands::Resume { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Print each setting's effective value and where it came from
+    Show,
+}
+
+impl Cli {
+    /// Settings given on the command line that override the config files.
+    pub fn overrides(&self) -> CliOverrides {
+        CliOverrides {
+            config: self.config.clone(),
+            scripts_dirs: self.scripts_dir.clone(),
+            log_dir: self.log_dir.clone(),
+            temp_root: self.temp_root.clone(),
+        }
+    }
+}
+
+/// Options shared by the commands that execute scripts
+#[derive(Args, Clone, Debug, Default)]
+pub struct RunOptions {
+    /// Profile from the config file providing scripts and defaults for these options
+    #[arg(short, long)]
+    pub profile: Option<String>,
+
+    /// Maximum number of independent scripts to run concurrently [default: 1, or `jobs` from the config]
+    #[arg(short, long)]
+    pub jobs: Option<NonZeroUsize>,
+
+    /// Show what would be executed without running any script
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
+
+    /// Keep running scripts that do not depend on a failed script
+    #[arg(short, long, default_value_t = false)]
+    pub keep_going: bool,
+
+    /// Number of most recent runs whose logs are kept [default: 10]
+    #[arg(long)]
+    pub keep_logs: Option<usize>,
+
+    /// Stop scripts that run longer than this (e.g. 90s, 30m, 2h), unless they set `@timeout`
+    #[arg(long, value_parser = parse_duration)]
+    pub timeout: Option<Duration>,
+
+    /// Run scripts even if their `@check` or check script reports them as already satisfied
+    #[arg(long, default_value_t = false)]
+    pub force: bool,
+
+    /// Extra environment variables for the scripts, set by profiles
+    #[arg(skip)]
+    pub env: BTreeMap<String, String>,
+
+    /// What the scripts are run for, set by the command
+    #[arg(skip)]
+    pub action: Action,
+}
+
+impl RunOptions {
+    pub fn jobs(&self) -> NonZeroUsize {
+        self.jobs.unwrap_or(NonZeroUsize::MIN)
+    }
+
+    pub fn keep_logs(&self) -> usize {
+        self.keep_logs.unwrap_or(DEFAULT_KEPT_RUNS)
+    }
+
+    /// Fills in the options not given on the command line from `profile`.
+    pub fn apply_profile(&mut self, profile: &Profile) {
+        self.jobs = self.jobs.or(profile.jobs);
+        self.keep_going |= profile.keep_going.unwrap_or(false);
+        self.keep_logs = self.keep_logs.or(profile.keep_logs);
+        self.timeout = self.timeout.or(profile.timeout);
+        for (key, value) in &profile.env {
+            self.env.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+    }
+}
+
 #[derive(clap::ValueEnum, Clone, Debug)]
 pub enum ListFormat {
     /// Simple plain text list
@@ -49,3 +278,14 @@ pub enum ListFormat {
     /// Table format
     Table,
 }
+
+impl ListFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ListFormat::Plain => "plain",
+            ListFormat::Json => "json",
+            ListFormat::Csv => "csv",
+            ListFormat::Table => "table",
+        }
+    }
+}
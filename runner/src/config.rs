@@ -0,0 +1,716 @@
+use anyhow::{anyhow, Context, Result};
+use log::debug;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::scripts::collector::DEFAULT_SCRIPTS_DIR;
+use crate::scripts::interpreter::DEFAULT_INTERPRETER;
+use crate::scripts::metadata::parse_duration;
+use crate::scripts::state::state_dir;
+use crate::scripts::target::OsFamilies;
+
+/// File name of the repository-local configuration.
+pub const CONFIG_FILE_NAME: &str = "runner.toml";
+
+/// Name of the system and user configuration files.
+const LAYER_FILE_NAME: &str = "config.toml";
+
+/// Contents of a configuration file. Every layer uses the same format:
+///
+/// ```toml
+/// scripts-dir = ["scripts", "~/private-scripts"]
+/// log-dir = "/var/log/runner"
+/// temp-root = "/var/tmp"
+/// default-interpreter = "bash"
+/// jobs = 4
+/// os = "debian"
+///
+/// [os-dirs]
+/// nobara = "fedora"
+///
+/// [os-fallbacks]
+/// pop = "ubuntu"
+///
+/// [profiles.base]
+/// scripts = ["git", "neovim"]
+///
+/// [profiles.workstation]
+/// extends = "base"
+/// scripts = ["nodejs", "tag:dev"]
+/// jobs = 4
+/// keep-going = true
+/// timeout = "30m"
+/// env = { NODE_VERSION = "22" }
+/// ```
+///
+/// Relative paths are resolved against the directory of the file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ConfigFile {
+    /// One or more script roots, later roots overlaying earlier ones.
+    pub scripts_dir: Option<OneOrMany<PathBuf>>,
+    pub log_dir: Option<PathBuf>,
+    pub temp_root: Option<PathBuf>,
+    pub default_interpreter: Option<String>,
+    pub jobs: Option<NonZeroUsize>,
+    pub os: Option<String>,
+    /// OS type to script directory, see [`OsFamilies::dirs`].
+    pub os_dirs: BTreeMap<String, String>,
+    /// Script directory to the one it falls back to, see
+    /// [`OsFamilies::fallbacks`].
+    pub os_fallbacks: BTreeMap<String, String>,
+    pub profiles: BTreeMap<String, ProfileConfig>,
+}
+
+impl ConfigFile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+        let mut file: ConfigFile = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+
+        let base = path.parent().unwrap_or(Path::new(""));
+        let scripts_dirs = file
+            .scripts_dir
+            .iter_mut()
+            .flat_map(|dirs| dirs.values_mut());
+        for dir in [&mut file.log_dir, &mut file.temp_root]
+            .into_iter()
+            .flatten()
+            .chain(scripts_dirs)
+        {
+            if dir.is_relative() {
+                *dir = base.join(&*dir);
+            }
+        }
+        Ok(file)
+    }
+}
+
+/// A named profile as written in the config file, before inheritance.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ProfileConfig {
+    /// Profiles whose settings this one starts from.
+    pub extends: Extends,
+    /// Script selectors, in the same syntax as `runner run`.
+    pub scripts: Vec<String>,
+    pub jobs: Option<NonZeroUsize>,
+    pub keep_going: Option<bool>,
+    pub keep_logs: Option<usize>,
+    pub timeout: Option<String>,
+    /// Environment variables passed to every script.
+    pub env: BTreeMap<String, String>,
+}
+
+/// `extends` accepts a single profile name or a list of them.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(untagged)]
+pub enum Extends {
+    #[default]
+    None,
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Extends {
+    fn names(&self) -> &[String] {
+        match self {
+            Extends::None => &[],
+            Extends::One(name) => std::slice::from_ref(name),
+            Extends::Many(names) => names,
+        }
+    }
+}
+
+/// A setting that accepts a single value or a list of them.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> OneOrMany<T> {
+    fn values_mut(&mut self) -> std::slice::IterMut<'_, T> {
+        match self {
+            OneOrMany::One(value) => std::slice::from_mut(value).iter_mut(),
+            OneOrMany::Many(values) => values.iter_mut(),
+        }
+    }
+
+    fn into_vec(self) -> Vec<T> {
+        match self {
+            OneOrMany::One(value) => vec![value],
+            OneOrMany::Many(values) => values,
+        }
+    }
+}
+
+/// A profile with its inherited settings merged in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+    pub scripts: Vec<String>,
+    pub jobs: Option<NonZeroUsize>,
+    pub keep_going: Option<bool>,
+    pub keep_logs: Option<usize>,
+    pub timeout: Option<Duration>,
+    pub env: BTreeMap<String, String>,
+}
+
+/// Where the effective value of a setting came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    Default,
+    System(PathBuf),
+    User(PathBuf),
+    Repository(PathBuf),
+    Environment(&'static str),
+    CommandLine,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Default => f.write_str("default"),
+            ConfigSource::System(path) => write!(f, "system config ({})", path.display()),
+            ConfigSource::User(path) => write!(f, "user config ({})", path.display()),
+            ConfigSource::Repository(path) => write!(f, "repository config ({})", path.display()),
+            ConfigSource::Environment(var) => write!(f, "environment ({})", var),
+            ConfigSource::CommandLine => f.write_str("command line"),
+        }
+    }
+}
+
+/// A configuration value together with its origin.
+#[derive(Debug, Clone)]
+pub struct Setting<T> {
+    pub value: T,
+    pub source: ConfigSource,
+}
+
+impl<T> Setting<T> {
+    fn new(value: T) -> Self {
+        Setting {
+            value,
+            source: ConfigSource::Default,
+        }
+    }
+
+    fn set(&mut self, value: Option<T>, source: &ConfigSource) {
+        if let Some(value) = value {
+            self.value = value;
+            self.source = source.clone();
+        }
+    }
+}
+
+/// Settings given on the command line, which take precedence over every
+/// configuration layer.
+#[derive(Debug, Clone, Default)]
+pub struct CliOverrides {
+    /// Replaces the repository-local config file.
+    pub config: Option<PathBuf>,
+    /// Script roots; replaces the configured ones unless empty.
+    pub scripts_dirs: Vec<PathBuf>,
+    pub log_dir: Option<PathBuf>,
+    pub temp_root: Option<PathBuf>,
+}
+
+/// Effective configuration, merged from (lowest to highest precedence) the
+/// built-in defaults, the system and user config files, the repository's
+/// `runner.toml`, `RUNNER_*` environment variables and command line flags.
+#[derive(Debug, Clone)]
+pub struct RunnerConfig {
+    /// Script roots in overlay order: scripts in later roots shadow scripts
+    /// with the same name and OS directory in earlier ones.
+    pub scripts_dirs: Setting<Vec<PathBuf>>,
+    pub log_dir: Setting<PathBuf>,
+    pub temp_root: Setting<PathBuf>,
+    pub default_interpreter: Setting<String>,
+    pub jobs: Setting<NonZeroUsize>,
+    /// OS script directory to use instead of the detected one.
+    pub os: Setting<Option<String>>,
+    /// OS directory mappings and fallbacks from every layer, on top of the
+    /// built-in ones.
+    pub os_families: OsFamilies,
+    /// Profiles from every layer; a profile in a higher layer replaces one
+    /// with the same name in a lower layer.
+    pub profiles: BTreeMap<String, ProfileConfig>,
+    /// Config files that were looked for, and whether they were found.
+    pub files: Vec<(ConfigSource, bool)>,
+}
+
+impl Default for RunnerConfig {
+    fn default() -> Self {
+        let log_dir = state_dir()
+            .map(|dir| dir.join("logs"))
+            .unwrap_or_else(|_| env::temp_dir().join("runner").join("logs"));
+        RunnerConfig {
+            scripts_dirs: Setting::new(vec![PathBuf::from(DEFAULT_SCRIPTS_DIR)]),
+            log_dir: Setting::new(log_dir),
+            temp_root: Setting::new(env::temp_dir()),
+            default_interpreter: Setting::new(String::from(DEFAULT_INTERPRETER)),
+            jobs: Setting::new(NonZeroUsize::MIN),
+            os: Setting::new(None),
+            os_families: OsFamilies::default(),
+            profiles: BTreeMap::new(),
+            files: Vec::new(),
+        }
+    }
+}
+
+impl RunnerConfig {
+    /// Loads and merges every configuration layer.
+    pub fn load(cli: &CliOverrides) -> Result<Self> {
+        let mut config = RunnerConfig::default();
+
+        let mut layers = Vec::new();
+        if let Some(path) = system_config_path() {
+            layers.push(ConfigSource::System(path));
+        }
+        if let Some(path) = user_config_path() {
+            layers.push(ConfigSource::User(path));
+        }
+        for source in layers {
+            config.apply_file(source, false)?;
+        }
+
+        // The repository config is found relative to the first scripts root,
+        // which the layers above, the environment or the command line may set.
+        let scripts_dir = cli
+            .scripts_dirs
+            .first()
+            .cloned()
+            .or_else(|| env_scripts_dirs().and_then(|dirs| dirs.into_iter().next()))
+            .or_else(|| config.scripts_dirs.value.first().cloned())
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_SCRIPTS_DIR));
+        match &cli.config {
+            Some(path) => config.apply_file(ConfigSource::Repository(path.clone()), true)?,
+            None => {
+                let path = find_repository_config(&scripts_dir);
+                config.apply_file(ConfigSource::Repository(path), false)?;
+            }
+        }
+
+        config.apply_environment()?;
+        config.apply_command_line(cli);
+        Ok(config)
+    }
+
+    /// Merges the config file of `source` if it exists. A missing file is
+    /// only an error if it was asked for explicitly.
+    fn apply_file(&mut self, source: ConfigSource, required: bool) -> Result<()> {
+        let path = match &source {
+            ConfigSource::System(path)
+            | ConfigSource::User(path)
+            | ConfigSource::Repository(path) => path.clone(),
+            _ => return Ok(()),
+        };
+        if !required && !path.is_file() {
+            debug!("No config file at {}", path.display());
+            self.files.push((source, false));
+            return Ok(());
+        }
+
+        debug!("Loading config file: {}", path.display());
+        let file = ConfigFile::load(&path)?;
+        self.scripts_dirs
+            .set(file.scripts_dir.map(OneOrMany::into_vec), &source);
+        self.log_dir.set(file.log_dir, &source);
+        self.temp_root.set(file.temp_root, &source);
+        self.default_interpreter
+            .set(file.default_interpreter, &source);
+        self.jobs.set(file.jobs, &source);
+        self.os.set(file.os.map(Some), &source);
+        self.os_families.dirs.extend(file.os_dirs);
+        self.os_families.fallbacks.extend(file.os_fallbacks);
+        self.profiles.extend(file.profiles);
+        self.files.push((source, true));
+        Ok(())
+    }
+
+    fn apply_environment(&mut self) -> Result<()> {
+        let var = |name: &'static str| {
+            env::var(name)
+                .ok()
+                .filter(|value| !value.is_empty())
+                .map(|value| (value, ConfigSource::Environment(name)))
+        };
+
+        if let Some(dirs) = env_scripts_dirs() {
+            let source = ConfigSource::Environment("RUNNER_SCRIPTS_DIR");
+            self.scripts_dirs.set(Some(dirs), &source);
+        }
+        if let Some((value, source)) = var("RUNNER_LOG_DIR") {
+            self.log_dir.set(Some(PathBuf::from(value)), &source);
+        }
+        if let Some((value, source)) = var("RUNNER_TEMP_ROOT") {
+            self.temp_root.set(Some(PathBuf::from(value)), &source);
+        }
+        if let Some((value, source)) = var("RUNNER_DEFAULT_INTERPRETER") {
+            self.default_interpreter.set(Some(value), &source);
+        }
+        if let Some((value, source)) = var("RUNNER_JOBS") {
+            let jobs = value.parse().map_err(|_| {
+                anyhow!(
+                    "Invalid RUNNER_JOBS '{}': expected a positive number",
+                    value
+                )
+            })?;
+            self.jobs.set(Some(jobs), &source);
+        }
+        if let Some((value, source)) = var("RUNNER_OS") {
+            self.os.set(Some(Some(value)), &source);
+        }
+        Ok(())
+    }
+
+    fn apply_command_line(&mut self, cli: &CliOverrides) {
+        let source = ConfigSource::CommandLine;
+        self.scripts_dirs.set(
+            Some(cli.scripts_dirs.clone()).filter(|dirs| !dirs.is_empty()),
+            &source,
+        );
+        self.log_dir.set(cli.log_dir.clone(), &source);
+        self.temp_root.set(cli.temp_root.clone(), &source);
+    }
+
+    /// Effective values and their sources, for display.
+    pub fn entries(&self) -> Vec<(&'static str, String, &ConfigSource)> {
+        vec![
+            (
+                "scripts-dir",
+                self.scripts_dirs
+                    .value
+                    .iter()
+                    .map(|dir| dir.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                &self.scripts_dirs.source,
+            ),
+            (
+                "log-dir",
+                self.log_dir.value.display().to_string(),
+                &self.log_dir.source,
+            ),
+            (
+                "temp-root",
+                self.temp_root.value.display().to_string(),
+                &self.temp_root.source,
+            ),
+            (
+                "default-interpreter",
+                self.default_interpreter.value.clone(),
+                &self.default_interpreter.source,
+            ),
+            ("jobs", self.jobs.value.to_string(), &self.jobs.source),
+            (
+                "os",
+                self.os
+                    .value
+                    .clone()
+                    .unwrap_or_else(|| String::from("(detected)")),
+                &self.os.source,
+            ),
+        ]
+    }
+
+    /// Resolves the profile `name`, applying the profiles it extends first.
+    ///
+    /// Selectors and environment variables accumulate along the chain, while
+    /// scalar settings of a profile override those it inherits.
+    pub fn profile(&self, name: &str) -> Result<Profile> {
+        let mut profile = Profile {
+            name: name.to_string(),
+            ..Profile::default()
+        };
+        self.merge_into(name, &mut profile, &mut Vec::new())?;
+        Ok(profile)
+    }
+
+    fn merge_into(&self, name: &str, profile: &mut Profile, chain: &mut Vec<String>) -> Result<()> {
+        if chain.iter().any(|visited| visited == name) {
+            chain.push(name.to_string());
+            return Err(anyhow!("Profile inheritance cycle: {}", chain.join(" -> ")));
+        }
+        let config = self.profiles.get(name).ok_or_else(|| {
+            anyhow!(
+                "Unknown profile '{}' (available: {})",
+                name,
+                self.profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+            )
+        })?;
+
+        chain.push(name.to_string());
+        for parent in config.extends.names() {
+            self.merge_into(parent, profile, chain)?;
+        }
+        chain.pop();
+
+        for selector in &config.scripts {
+            if !profile.scripts.contains(selector) {
+                profile.scripts.push(selector.clone());
+            }
+        }
+        if config.jobs.is_some() {
+            profile.jobs = config.jobs;
+        }
+        if config.keep_going.is_some() {
+            profile.keep_going = config.keep_going;
+        }
+        if config.keep_logs.is_some() {
+            profile.keep_logs = config.keep_logs;
+        }
+        if let Some(timeout) = &config.timeout {
+            profile.timeout = Some(
+                parse_duration(timeout)
+                    .map_err(|e| anyhow!("Invalid timeout in profile '{}': {}", name, e))?,
+            );
+        }
+        profile.env.extend(config.env.clone());
+        Ok(())
+    }
+}
+
+/// Script roots from `RUNNER_SCRIPTS_DIR`, separated like `PATH`.
+fn env_scripts_dirs() -> Option<Vec<PathBuf>> {
+    let value = env::var_os("RUNNER_SCRIPTS_DIR").filter(|value| !value.is_empty())?;
+    Some(env::split_paths(&value).collect())
+}
+
+/// `/etc/runner/config.toml`, or `%ProgramData%\runner\config.toml` on Windows.
+fn system_config_path() -> Option<PathBuf> {
+    if cfg!(windows) {
+        env::var_os("ProgramData")
+            .map(|dir| PathBuf::from(dir).join("runner").join(LAYER_FILE_NAME))
+    } else {
+        Some(PathBuf::from("/etc/runner").join(LAYER_FILE_NAME))
+    }
+}
+
+/// `$XDG_CONFIG_HOME/runner/config.toml` (falling back to `~/.config`), or
+/// `%APPDATA%\runner\config.toml` on Windows.
+fn user_config_path() -> Option<PathBuf> {
+    let dir = if let Some(dir) = env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        PathBuf::from(dir)
+    } else if let Some(dir) = env::var_os("APPDATA").filter(|_| cfg!(windows)) {
+        PathBuf::from(dir)
+    } else {
+        PathBuf::from(env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))?).join(".config")
+    };
+    Some(dir.join("runner").join(LAYER_FILE_NAME))
+}
+
+/// The first `runner.toml` in the current directory, next to the scripts
+/// directory or inside it. Defaults to the current directory if none exists.
+fn find_repository_config(scripts_dir: &Path) -> PathBuf {
+    let mut candidates = vec![PathBuf::from(CONFIG_FILE_NAME)];
+    if let Some(parent) = scripts_dir.parent() {
+        candidates.push(parent.join(CONFIG_FILE_NAME));
+    }
+    candidates.push(scripts_dir.join(CONFIG_FILE_NAME));
+    candidates
+        .into_iter()
+        .find(|path| path.is_file())
+        .unwrap_or_else(|| PathBuf::from(CONFIG_FILE_NAME))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(toml: &str) -> RunnerConfig {
+        let file: ConfigFile = toml::from_str(toml).unwrap();
+        RunnerConfig {
+            profiles: file.profiles,
+            ..RunnerConfig::default()
+        }
+    }
+
+    #[test]
+    fn merges_extended_profiles() {
+        let config = config(
+            r#"
+            [profiles.base]
+            scripts = ["git", "neovim"]
+            jobs = 2
+            keep-going = true
+            env = { EDITOR = "nvim", LANG = "C" }
+
+            [profiles.node]
+            scripts = ["nodejs"]
+            timeout = "30m"
+
+            [profiles.workstation]
+            extends = ["base", "node"]
+            scripts = ["neovim", "tag:dev"]
+            jobs = 4
+            env = { LANG = "en_US.UTF-8" }
+            "#,
+        );
+
+        let profile = config.profile("workstation").unwrap();
+        assert_eq!(profile.scripts, ["git", "neovim", "nodejs", "tag:dev"]);
+        assert_eq!(profile.jobs, NonZeroUsize::new(4));
+        assert_eq!(profile.keep_going, Some(true));
+        assert_eq!(profile.timeout, Some(Duration::from_secs(30 * 60)));
+        assert_eq!(profile.env["EDITOR"], "nvim");
+        assert_eq!(profile.env["LANG"], "en_US.UTF-8");
+
+        let base = config.profile("base").unwrap();
+        assert_eq!(base.scripts, ["git", "neovim"]);
+        assert_eq!(base.timeout, None);
+    }
+
+    #[test]
+    fn rejects_inheritance_cycles_and_unknown_profiles() {
+        let config = config(
+            r#"
+            [profiles.a]
+            extends = "b"
+            [profiles.b]
+            extends = "c"
+            [profiles.c]
+            extends = "a"
+            [profiles.d]
+            extends = "missing"
+            "#,
+        );
+        assert_eq!(
+            config.profile("a").unwrap_err().to_string(),
+            "Profile inheritance cycle: a -> b -> c -> a"
+        );
+        assert_eq!(
+            config.profile("d").unwrap_err().to_string(),
+            "Unknown profile 'missing' (available: a, b, c, d)"
+        );
+    }
+
+    #[test]
+    fn applies_layers_in_order_of_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, content: &str| {
+            let path = dir.path().join(name);
+            fs::write(&path, content).unwrap();
+            path
+        };
+        let system = write(
+            "system.toml",
+            r#"
+            jobs = 2
+            log-dir = "/var/log/runner"
+            default-interpreter = "sh"
+            os = "debian"
+            [os-fallbacks]
+            nobara = "fedora"
+            [profiles.dev]
+            scripts = ["git"]
+            "#,
+        );
+        let user = write(
+            "user.toml",
+            r#"
+            jobs = 3
+            temp-root = "tmp"
+            default-interpreter = "bash"
+            [os-dirs]
+            macos = "mac"
+            [profiles.dev]
+            scripts = ["neovim"]
+            "#,
+        );
+        let repository = write(
+            "runner.toml",
+            "jobs = 4\nscripts-dir = [\"scripts\", \"/opt/private\"]\n",
+        );
+
+        let mut config = RunnerConfig::default();
+        config
+            .apply_file(ConfigSource::System(system.clone()), false)
+            .unwrap();
+        config
+            .apply_file(ConfigSource::User(user.clone()), false)
+            .unwrap();
+        config
+            .apply_file(ConfigSource::Repository(repository.clone()), false)
+            .unwrap();
+        config
+            .apply_file(
+                ConfigSource::Repository(dir.path().join("missing.toml")),
+                false,
+            )
+            .unwrap();
+        // Only this test sets RUNNER_* variables.
+        env::set_var("RUNNER_DEFAULT_INTERPRETER", "zsh");
+        let applied = config.apply_environment();
+        env::remove_var("RUNNER_DEFAULT_INTERPRETER");
+        applied.unwrap();
+        config.apply_command_line(&CliOverrides {
+            log_dir: Some(PathBuf::from("/tmp/logs")),
+            ..CliOverrides::default()
+        });
+
+        assert_eq!(config.os.value.as_deref(), Some("debian"));
+        assert_eq!(config.os.source, ConfigSource::System(system));
+        assert_eq!(config.temp_root.value, dir.path().join("tmp"));
+        assert_eq!(config.temp_root.source, ConfigSource::User(user));
+        assert_eq!(config.jobs.value.get(), 4);
+        assert_eq!(
+            config.scripts_dirs.value,
+            [dir.path().join("scripts"), PathBuf::from("/opt/private")]
+        );
+        assert_eq!(
+            config.scripts_dirs.source,
+            ConfigSource::Repository(repository)
+        );
+        assert_eq!(config.default_interpreter.value, "zsh");
+        assert_eq!(
+            config.default_interpreter.source,
+            ConfigSource::Environment("RUNNER_DEFAULT_INTERPRETER")
+        );
+        assert_eq!(config.log_dir.value, PathBuf::from("/tmp/logs"));
+        assert_eq!(config.log_dir.source, ConfigSource::CommandLine);
+
+        // Mappings accumulate, while a profile is replaced as a whole.
+        assert_eq!(config.os_families.fallbacks["nobara"], "fedora");
+        assert_eq!(config.os_families.dirs["macos"], "mac");
+        assert_eq!(config.profile("dev").unwrap().scripts, ["neovim"]);
+        let found: Vec<bool> = config.files.iter().map(|(_, found)| *found).collect();
+        assert_eq!(found, [true, true, true, false]);
+    }
+
+    #[test]
+    fn requires_explicitly_given_config_files() {
+        let mut config = RunnerConfig::default();
+        let missing = PathBuf::from("/nonexistent/runner.toml");
+        assert!(config
+            .apply_file(ConfigSource::Repository(missing), true)
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_config_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("runner.toml");
+        fs::write(&path, "job = 4\n").unwrap();
+        assert!(ConfigFile::load(&path).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_profile_timeouts() {
+        let config = config("[profiles.slow]\ntimeout = \"forever\"\n");
+        assert!(config
+            .profile("slow")
+            .unwrap_err()
+            .to_string()
+            .starts_with("Invalid timeout in profile 'slow'"));
+    }
+}
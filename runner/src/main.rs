@@ -1,11 +1,18 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use clap::Parser;
 use log::{debug, info, warn};
-use runner::cli::commands::{interactive_mode, list_mode, run_specified_scripts};
+use runner::cli::commands::{
+    config_show_mode, doctor_mode, interactive_mode, inventory_mode, list_mode, logs_mode,
+    plan_mode, remove_mode, resume_mode, run_specified_scripts, shellenv_mode, status_mode,
+    upgrade_mode,
+};
 use runner::cli::Cli;
-use runner::cli::Commands;
+use runner::cli::{Commands, ConfigAction, RunOptions};
+use runner::config::RunnerConfig;
 use runner::scripts::collector::collect_scripts;
+use runner::scripts::context::RuntimeContext;
 use runner::scripts::embedded;
+use runner::scripts::target::Target;
 
 fn main() -> Result<()> {
     // Initialize the logger with default level INFO
@@ -13,24 +20,76 @@ fn main() -> Result<()> {
 
     debug!("Parsing command line arguments");
     let cli: Cli = Cli::parse();
+    let config = RunnerConfig::load(&cli.overrides())?;
+
+    // Inspecting the configuration, shell settings and logs does not need any
+    // scripts
+    match cli.command {
+        Some(Commands::Config { action }) => {
+            return match action {
+                ConfigAction::Show => config_show_mode(&config),
+            };
+        }
+        Some(Commands::Shellenv { shell }) => return shellenv_mode(shell),
+        Some(Commands::Logs {
+            run_id,
+            script,
+            tail,
+        }) => return logs_mode(run_id, script, tail, &config),
+        _ => {}
+    }
+
     let os_info = os_info::get();
 
-    info!("Detected OS: {}", os_info);
+    let context = RuntimeContext::current();
+    if context.detected.is_empty() {
+        info!("Detected OS: {}", os_info);
+    } else {
+        info!("Detected OS: {} in {}", os_info, context);
+    }
+
+    let os_override = config.os.value.as_deref();
+    if let Some(os_dir) = os_override {
+        info!("Using OS script directory override: {}", os_dir);
+    }
+    let native = Target::detect(&os_info, os_override, &config.os_families);
+    let target = native.simulate(cli.os.as_deref(), cli.arch.as_deref(), &config.os_families)?;
 
-    // Try to collect scripts from the specified directory
-    debug!("Collecting scripts from: {}", cli.scripts_dir.display());
-    let scripts = collect_scripts(Some(&cli.scripts_dir), os_info.os_type());
+    let default_command = Commands::Interactive {
+        all: false,
+        options: RunOptions::default(),
+    };
+    let command = cli.command.unwrap_or(default_command);
+    if target != native {
+        if command.executes_scripts() && !cli.force_platform {
+            bail!(
+                "Refusing to run scripts for {} on {}; use --dry-run to review them, or --force-platform to run them anyway",
+                target,
+                native
+            );
+        }
+        info!(
+            "Collecting scripts as if on {} instead of {}",
+            target, native
+        );
+    }
+
+    // Try to collect scripts from the configured directory
+    let scripts_dirs = &config.scripts_dirs.value;
+    let scripts = collect_scripts(scripts_dirs, &target);
 
     // If external scripts are available, use them; otherwise use embedded ones
     let scripts = match scripts {
         Ok(scripts) if !scripts.is_empty() => {
-            info!("Using external scripts from: {}", cli.scripts_dir.display());
+            for scripts_dir in scripts_dirs {
+                info!("Using external scripts from: {}", scripts_dir.display());
+            }
             scripts
         }
         _ => {
             info!("No external scripts found, checking for embedded scripts");
             if embedded::has_embedded_scripts() {
-                match embedded::extract_embedded_scripts(os_info.os_type()) {
+                match embedded::extract_embedded_scripts(&target) {
                     Ok(scripts) => {
                         if scripts.is_empty() {
                             warn!("No applicable scripts found in embedded scripts");
@@ -54,12 +113,44 @@ fn main() -> Result<()> {
     info!("Found {} scripts", scripts.len());
     debug!("Scripts: {:?}", scripts);
 
-    match cli.command.unwrap_or(Commands::Interactive { all: false }) {
-        Commands::Interactive { all } => interactive_mode(&scripts, all)?,
+    match command {
+        Commands::Interactive { all, options } => {
+            interactive_mode(&scripts, all, options, &config)?
+        }
         Commands::Run {
             scripts: script_names,
-        } => run_specified_scripts(&scripts, script_names)?,
-        Commands::List { format } => list_mode(&scripts, format)?,
+            options,
+        } => run_specified_scripts(&scripts, script_names, options, &config)?,
+        Commands::Remove {
+            scripts: script_names,
+            ignore_dependents,
+            options,
+        } => remove_mode(&scripts, script_names, ignore_dependents, options, &config)?,
+        Commands::Upgrade {
+            scripts: script_names,
+            options,
+        } => upgrade_mode(&scripts, script_names, options, &config)?,
+        Commands::Plan {
+            scripts: script_names,
+        } => plan_mode(&scripts, script_names)?,
+        Commands::Resume {
+            run_id,
+            skip_failed,
+        } => resume_mode(&scripts, run_id, skip_failed, &config)?,
+        Commands::Status => status_mode(&scripts)?,
+        Commands::Doctor {
+            scripts: script_names,
+            all,
+            no_version,
+        } => doctor_mode(&scripts, script_names, all, no_version)?,
+        Commands::Inventory {
+            scripts: script_names,
+            format,
+        } => inventory_mode(&scripts, script_names, format)?,
+        Commands::List { format } => list_mode(&scripts, format, &config)?,
+        Commands::Config { .. } | Commands::Shellenv { .. } | Commands::Logs { .. } => {
+            unreachable!("handled before collecting scripts")
+        }
     }
 
     Ok(())
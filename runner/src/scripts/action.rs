@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::scripts::models::ScriptInfo;
+
+/// What running a script is meant to achieve, passed to it as
+/// `RUNNER_ACTION`.
+///
+/// Scripts install by default. Other actions are performed by a companion
+/// script (`nodejs.remove.sh`) or, if the script lists the action in
+/// `@actions`, by the script itself.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    #[default]
+    Install,
+    /// Undo what the script installed.
+    Remove,
+    /// Bring what the script installed up to date, falling back to
+    /// installing again.
+    Upgrade,
+}
+
+impl Action {
+    pub const ALL: [Action; 3] = [Action::Install, Action::Remove, Action::Upgrade];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Install => "install",
+            Action::Remove => "remove",
+            Action::Upgrade => "upgrade",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Action::ALL
+            .into_iter()
+            .find(|action| action.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn is_install(&self) -> bool {
+        *self == Action::Install
+    }
+
+    /// The action actually performed for `script` and the script to execute
+    /// for it: a companion script, or `script` itself if it installs or
+    /// declares the action in `@actions`. Scripts without an upgrade action
+    /// are installed again; `None` if the script cannot perform the action.
+    pub fn resolve(self, script: &ScriptInfo) -> Option<(Action, ScriptInfo)> {
+        let companion = match self {
+            Action::Install => return Some((self, script.clone())),
+            Action::Remove => &script.companions.remove,
+            Action::Upgrade => &script.companions.upgrade,
+        };
+        if let Some(path) = companion {
+            let mut companion = ScriptInfo::from_path(path);
+            companion.name = script.name.clone();
+            companion.os_type = script.os_type.clone();
+            companion.root = script.root.clone();
+            return Some((self, companion));
+        }
+        if script
+            .metadata
+            .actions
+            .iter()
+            .any(|action| action == self.name())
+        {
+            return Some((self, script.clone()));
+        }
+        match self {
+            Action::Upgrade => Action::Install.resolve(script),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
@@ -0,0 +1,155 @@
+use anyhow::{Context, Result};
+use log::debug;
+use std::fmt;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::scripts::interpreter::require_interpreter;
+use crate::scripts::models::ScriptInfo;
+use crate::scripts::process;
+
+/// Time a check gets before it counts as not satisfied.
+pub const CHECK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A way of telling whether a script still needs to run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Check {
+    /// A `@check` shell command.
+    Command(String),
+    /// A companion `name.check.sh` script.
+    Script(PathBuf),
+}
+
+impl Check {
+    /// The checks of `script`: its `@check` command, then its companion
+    /// check script.
+    pub fn of(script: &ScriptInfo) -> Vec<Check> {
+        let command = script.metadata.check.clone().map(Check::Command);
+        let companion = script.companions.check.clone().map(Check::Script);
+        command.into_iter().chain(companion).collect()
+    }
+
+    fn command(&self, default_interpreter: &str) -> Result<Command> {
+        match self {
+            Check::Command(command_line) => {
+                let mut command = if cfg!(windows) {
+                    let mut command = Command::new("pwsh");
+                    command.args(["-NoProfile", "-Command"]);
+                    command
+                } else {
+                    let mut command = Command::new("sh");
+                    command.arg("-c");
+                    command
+                };
+                command.arg(command_line);
+                Ok(command)
+            }
+            Check::Script(path) => {
+                let check = ScriptInfo::from_path(path);
+                let (interpreter, program) = require_interpreter(&check, default_interpreter)?;
+                let mut command = Command::new(program);
+                command.args(&interpreter.args).arg(path);
+                Ok(command)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Check::Command(command_line) => write!(f, "@check `{}`", command_line),
+            Check::Script(path) => match path.file_name() {
+                Some(name) => write!(f, "{}", name.to_string_lossy()),
+                None => write!(f, "{}", path.display()),
+            },
+        }
+    }
+}
+
+/// Outcome of running the checks of a script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckResult {
+    /// Every check passed, so the script does not need to run.
+    Satisfied(String),
+    /// A check failed, so the script runs.
+    NotSatisfied(String),
+    /// The run was interrupted while checking, so the script does not run.
+    Cancelled(String),
+}
+
+/// Runs the checks of `script` in order until one fails. Returns `None` if
+/// the script has no checks.
+///
+/// `prepare` sets up the working directory and environment the script itself
+/// would get. Check output is discarded; a check that does not finish within
+/// [`CHECK_TIMEOUT`] counts as failed, one stopped by an interrupt as
+/// cancelled.
+pub fn run_checks(
+    script: &ScriptInfo,
+    default_interpreter: &str,
+    prepare: impl Fn(&mut Command) -> Result<()>,
+) -> Result<Option<CheckResult>> {
+    let checks = Check::of(script);
+    if checks.is_empty() {
+        return Ok(None);
+    }
+
+    for check in &checks {
+        let mut command = check.command(default_interpreter)?;
+        prepare(&mut command)?;
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        process::isolate(&mut command, false);
+
+        debug!("Running {} for {}", check, script.display_name());
+        let mut child = command
+            .spawn()
+            .with_context(|| format!("Failed to run {}", check))?;
+        let deadline = Instant::now() + CHECK_TIMEOUT;
+        let failure = loop {
+            if let Some(status) = child
+                .try_wait()
+                .with_context(|| format!("Failed to wait for {}", check))?
+            {
+                break match status.code() {
+                    Some(0) => None,
+                    Some(code) => Some(CheckResult::NotSatisfied(format!(
+                        "{} exited with {}",
+                        check, code
+                    ))),
+                    None => Some(CheckResult::NotSatisfied(format!(
+                        "{} was terminated by a signal",
+                        check
+                    ))),
+                };
+            }
+            if process::is_interrupted() {
+                process::terminate(&mut child, process::TERMINATION_GRACE_PERIOD)?;
+                break Some(CheckResult::Cancelled(format!("{} was cancelled", check)));
+            }
+            if Instant::now() >= deadline {
+                process::terminate(&mut child, process::TERMINATION_GRACE_PERIOD)?;
+                break Some(CheckResult::NotSatisfied(format!(
+                    "{} did not finish within {}s",
+                    check,
+                    CHECK_TIMEOUT.as_secs()
+                )));
+            }
+            thread::sleep(process::POLL_INTERVAL);
+        };
+        if failure.is_some() {
+            return Ok(failure);
+        }
+    }
+
+    let passed: Vec<String> = checks.iter().map(ToString::to_string).collect();
+    Ok(Some(CheckResult::Satisfied(format!(
+        "{} passed",
+        passed.join(", ")
+    ))))
+}
@@ -1,117 +1,363 @@
-use anyhow::{Error, Result};
+use anyhow::Result;
 use log::{debug, info, warn};
-use os_info::Type as OsType;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
-/// Collects all the scripts in the given directory and its subdirectories.
+use crate::scripts::interpreter::SCRIPT_EXTENSIONS;
+use crate::scripts::models::{is_companion_file, Companions, ScriptInfo};
+use crate::scripts::selector::qualified_name;
+use crate::scripts::target::{Target, VariantMatch};
+
+/// Scripts directory used when none is configured.
+pub const DEFAULT_SCRIPTS_DIR: &str = "../scripts";
+
+/// Collects all the scripts in the given directories and their
+/// subdirectories, parsing the metadata header of each one.
+///
+/// Within a directory, version and architecture variants of a script
+/// (`debian/12/nodejs.sh`, `debian/arm64/nodejs.sh`, `debian/nodejs.arm64.sh`)
+/// are matched against `target` and only the most specific match is kept.
+///
+/// The OS directories of `target` form a fallback chain (`pop`, `ubuntu`,
+/// `debian`, `unix`): a script in a more specific directory replaces the
+/// script with the same name in the ones after it.
+///
+/// The scripts directories are overlaid in order: a script in a later directory
+/// replaces the script with the same name in the same OS directory of an
+/// earlier one, and is recorded as shadowing it.
 ///
 /// # Arguments
-/// * `scripts_dir` - The directory containing scripts. Defaults to "../scripts" if None is provided.
-/// * `os_type` - The operating system type to determine OS-specific scripts.
-pub fn collect_scripts(scripts_dir: Option<&Path>, os_type: OsType) -> Result<Vec<PathBuf>> {
-    let scripts_dir = scripts_dir.unwrap_or_else(|| Path::new("../scripts"));
+/// * `scripts_dirs` - The directories containing scripts. Defaults to [`DEFAULT_SCRIPTS_DIR`] if empty.
+/// * `target` - The OS directory, version and architecture to collect scripts for.
+pub fn collect_scripts(scripts_dirs: &[PathBuf], target: &Target) -> Result<Vec<ScriptInfo>> {
+    let default_dirs = [PathBuf::from(DEFAULT_SCRIPTS_DIR)];
+    let scripts_dirs = if scripts_dirs.is_empty() {
+        &default_dirs[..]
+    } else {
+        scripts_dirs
+    };
+
+    info!(
+        "Collecting scripts for {} from {}",
+        target,
+        target.os_dirs.join(" -> ")
+    );
+
+    if !has_os_specific_dirs(scripts_dirs, target) {
+        warn!(
+            "{} is not specifically supported: no scripts directory has a {} directory",
+            target,
+            specific_os_dirs(target).collect::<Vec<_>>().join(" or ")
+        );
+    }
+
+    let mut collected: Vec<ScriptInfo> = Vec::new();
+    for scripts_dir in scripts_dirs {
+        if scripts_dirs.len() > 1 && !scripts_dir.is_dir() {
+            warn!("Scripts directory not found: {}", scripts_dir.display());
+            continue;
+        }
+        for mut script in collect_root(scripts_dir, target) {
+            script.root = scripts_dir.clone();
+            match collected
+                .iter_mut()
+                .find(|other| other.name == script.name && other.os_type == script.os_type)
+            {
+                Some(shadowed) => {
+                    info!(
+                        "{} shadows {}",
+                        script.path.display(),
+                        shadowed.path.display()
+                    );
+                    script.shadows = std::mem::take(&mut shadowed.shadows);
+                    script.shadows.push(shadowed.path.clone());
+                    *shadowed = script;
+                }
+                None => collected.push(script),
+            }
+        }
+    }
+
+    // Sorted by name as a side effect.
+    let collected = apply_fallbacks(collected, target);
+    info!("Collected {} scripts total", collected.len());
+    Ok(collected)
+}
+
+/// Scripts of a single scripts directory that apply to `target`, from every
+/// directory of its OS chain.
+fn collect_root(scripts_dir: &Path, target: &Target) -> Vec<ScriptInfo> {
     debug!(
         "Collecting scripts from directory: {}",
         scripts_dir.display()
     );
     let mut scripts = Vec::new();
 
-    let os_dir = match resolve_os_script_directory(os_type) {
-        Ok(value) => value,
-        Err(error) => {
-            warn!(
-                "Error resolving OS script directory: {}. Attempting generic unix scripts.",
-                error
-            );
-            String::from("unix")
+    for os_dir in &target.os_dirs {
+        let dir = scripts_dir.join(os_dir);
+        if !dir.is_dir() {
+            debug!("OS script directory not found: {}", dir.display());
+            continue;
         }
-    };
-
-    if os_type != OsType::Windows {
-        collect_unix_scripts(scripts_dir)
-            .into_iter()
-            .for_each(|script| scripts.push(script));
+        info!("Found OS script directory: {}", dir.display());
+        scripts.extend(select_variants(&dir, collect_dir_scripts(&dir), target));
     }
 
-    let os_specific_scripts = collect_os_specific_scripts(scripts_dir, os_dir.as_str());
-    if !os_specific_scripts.is_empty() {
-        scripts.extend(os_specific_scripts);
+    if scripts.is_empty() {
+        warn!(
+            "No scripts for {} in {}",
+            target.os_dirs.join(", "),
+            scripts_dir.display()
+        );
     }
+    scripts
+}
 
-    scripts.sort_by(|a, b| {
-        let a_name = a.file_name().unwrap().to_string_lossy().to_string();
-        let b_name = b.file_name().unwrap().to_string_lossy().to_string();
-        a_name.cmp(&b_name)
-    });
-    info!("Collected {} scripts total", scripts.len());
-    Ok(scripts)
+/// Whether a scripts directory has one of the OS directories of `target`, or
+/// the target only uses the generic ones.
+fn has_os_specific_dirs(scripts_dirs: &[PathBuf], target: &Target) -> bool {
+    let mut specific = specific_os_dirs(target).peekable();
+    specific.peek().is_none()
+        || specific.any(|os_dir| scripts_dirs.iter().any(|root| root.join(os_dir).is_dir()))
 }
 
-fn collect_unix_scripts(scripts_dir: &Path) -> Vec<PathBuf> {
+/// The OS directories of `target` besides `unix`, which most chains end with.
+fn specific_os_dirs(target: &Target) -> impl Iterator<Item = &str> {
+    target
+        .os_dirs
+        .iter()
+        .map(String::as_str)
+        .filter(|dir| *dir != "unix")
+}
+
+/// Keeps, for every script name, only the script from the earliest OS
+/// directory of the target's chain, and records the ones it replaces.
+fn apply_fallbacks(mut scripts: Vec<ScriptInfo>, target: &Target) -> Vec<ScriptInfo> {
+    let rank = |script: &ScriptInfo| {
+        target
+            .os_dirs
+            .iter()
+            .position(|dir| *dir == script.os_type)
+            .unwrap_or(usize::MAX)
+    };
+    scripts.sort_by_key(|script| (script.name.clone(), rank(script)));
+
+    scripts
+        .chunk_by(|a, b| a.name == b.name)
+        .map(|group| {
+            let mut script = group[0].clone();
+            if group.len() > 1 {
+                let overridden: Vec<String> = group[1..]
+                    .iter()
+                    .map(|other| {
+                        let relative = other.path.strip_prefix(&other.root).unwrap_or(&other.path);
+                        relative.display().to_string()
+                    })
+                    .collect();
+                debug!(
+                    "{} takes precedence over {}",
+                    script.path.display(),
+                    overridden.join(", ")
+                );
+                let separator = if script.reason.contains("preferred over") {
+                    ", "
+                } else {
+                    "; preferred over "
+                };
+                script.reason.push_str(separator);
+                script.reason.push_str(&overridden.join(", "));
+                script.overrides = group[1..].iter().map(qualified_name).collect();
+            }
+            script
+        })
+        .collect()
+}
+
+/// A script file that matched the target, before picking among variants.
+struct Candidate {
+    path: PathBuf,
+    relative: String,
+    qualifiers: Vec<String>,
+    specificity: usize,
+}
+
+/// Keeps, for every script name in `os_dir`, the variant among `paths` that
+/// matches `target` most specifically, and records why it was chosen.
+fn select_variants(os_dir: &Path, mut paths: Vec<PathBuf>, target: &Target) -> Vec<ScriptInfo> {
+    let os_dir_name = os_dir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    paths.sort();
+
+    let mut candidates: Vec<(String, Vec<Candidate>)> = Vec::new();
+    for path in paths {
+        let relative = path.strip_prefix(os_dir).unwrap_or(&path);
+        match target.match_variant(relative) {
+            VariantMatch::Match {
+                name,
+                qualifiers,
+                specificity,
+            } => {
+                let candidate = Candidate {
+                    relative: format!("{}/{}", os_dir_name, relative.to_string_lossy()),
+                    path,
+                    qualifiers: qualifiers.iter().map(ToString::to_string).collect(),
+                    specificity,
+                };
+                match candidates.iter_mut().find(|(other, _)| *other == name) {
+                    Some((_, variants)) => variants.push(candidate),
+                    None => candidates.push((name, vec![candidate])),
+                }
+            }
+            VariantMatch::Mismatch(reason) => {
+                debug!("Skipping {}: {}", path.display(), reason);
+            }
+        }
+    }
+
     let mut scripts = Vec::new();
-    let unix_dir = scripts_dir.join("unix");
-    if unix_dir.exists() {
-        for entry in WalkDir::new(&unix_dir)
-            .min_depth(1)
-            .into_iter()
-            .filter_map(|e| e.ok())
+    for (name, mut variants) in candidates {
+        // Stable sort, so ties keep path order and the first one wins.
+        variants.sort_by_key(|variant| std::cmp::Reverse(variant.specificity));
+        let chosen = variants.remove(0);
+        if let Some(tied) = variants
+            .iter()
+            .find(|v| v.specificity == chosen.specificity)
         {
-            if entry.path().extension().is_some_and(|ext| ext == "sh") {
-                debug!("Found Unix-compatible script: {}", entry.path().display());
-                scripts.push(entry.path().to_path_buf());
-            }
+            warn!(
+                "{} and {} are equally specific for {}, using {}",
+                chosen.relative, tied.relative, target, chosen.relative
+            );
         }
+
+        let mut reason = if chosen.qualifiers.is_empty() {
+            format!("generic {} script", os_dir_name)
+        } else {
+            format!("matches {}", chosen.qualifiers.join(", "))
+        };
+        if !variants.is_empty() {
+            reason.push_str(&format!(
+                "; preferred over {}",
+                variants
+                    .iter()
+                    .map(|v| v.relative.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        if !chosen.qualifiers.is_empty() {
+            info!("Using {} for {}: {}", chosen.relative, name, reason);
+        }
+
+        let mut script = ScriptInfo::from_path(&chosen.path);
+        script.companions = Companions::find(&chosen.path, &name);
+        script.name = name;
+        script.os_type = os_dir_name.clone();
+        script.reason = reason;
+        scripts.push(script);
     }
 
     scripts
 }
 
-fn collect_os_specific_scripts(scripts_dir: &Path, os_dir: &str) -> Vec<PathBuf> {
+fn collect_dir_scripts(dir: &Path) -> Vec<PathBuf> {
     let mut scripts = Vec::new();
-    let os_specific_dir = scripts_dir.join(os_dir);
-
-    debug!(
-        "Looking for OS-specific scripts in: {}",
-        os_specific_dir.display()
-    );
-    if os_specific_dir.exists() {
-        info!(
-            "Found OS-specific scripts directory: {}",
-            os_specific_dir.display()
-        );
-        for entry in WalkDir::new(&os_specific_dir)
-            .min_depth(1)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            if entry.path().extension().is_some_and(|ext| ext == "sh") {
-                debug!("Found OS-specific script: {}", entry.path().display());
-                scripts.push(entry.path().to_path_buf());
-            }
+    for entry in WalkDir::new(dir)
+        .min_depth(1)
+        .into_iter()
+        .filter_map(|e| e.ok())
+    {
+        if is_script_file(entry.path()) {
+            debug!("Found script: {}", entry.path().display());
+            scripts.push(entry.path().to_path_buf());
         }
-    } else {
-        warn!(
-            "OS-specific directory not found: {}",
-            os_specific_dir.display()
-        );
     }
-
     scripts
 }
 
-fn resolve_os_script_directory(os_type: OsType) -> anyhow::Result<String> {
-    let os_dir = match os_type {
-        OsType::Ubuntu | OsType::Pop | OsType::Raspbian | OsType::Kali | OsType::Debian => "debian",
-        OsType::Macos => "darwin",
-        OsType::Alpine => "alpine",
-        OsType::Windows => "windows",
-        _ => {
-            return Err(Error::msg(format!(
-                "OS type {:?} not specifically supported, using only root scripts",
-                os_type
-            )));
+/// Whether `path` has one of the extensions listed in [`SCRIPT_EXTENSIONS`]
+/// and is not the companion of another script.
+fn is_script_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| {
+        SCRIPT_EXTENSIONS
+            .iter()
+            .any(|supported| ext.eq_ignore_ascii_case(supported))
+    }) && !is_companion_file(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn target(os_dirs: &[&str]) -> Target {
+        Target {
+            os_dirs: os_dirs.iter().map(|dir| dir.to_string()).collect(),
+            version: None,
+            arch: String::from("amd64"),
         }
-    };
-    Ok(os_dir.to_string())
+    }
+
+    #[test]
+    fn notices_systems_without_os_specific_directories() {
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        fs::create_dir(first.path().join("unix")).unwrap();
+        fs::create_dir(second.path().join("redhat")).unwrap();
+        let roots = [first.path().to_path_buf(), second.path().to_path_buf()];
+
+        assert!(!has_os_specific_dirs(
+            &roots[..1],
+            &target(&["gentoo", "unix"])
+        ));
+        assert!(!has_os_specific_dirs(&roots, &target(&["gentoo", "unix"])));
+        assert!(has_os_specific_dirs(
+            &roots,
+            &target(&["amazon", "redhat", "unix"])
+        ));
+        assert!(has_os_specific_dirs(&roots, &target(&["unix"])));
+        assert!(!has_os_specific_dirs(&roots, &target(&["windows"])));
+    }
+
+    #[test]
+    fn overlays_later_script_roots_on_earlier_ones() {
+        let roots = [
+            tempfile::tempdir().unwrap(),
+            tempfile::tempdir().unwrap(),
+            tempfile::tempdir().unwrap(),
+        ];
+        let write = |root: usize, path: &str| {
+            let path = roots[root].path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, "echo hi\n").unwrap();
+            path
+        };
+        let first_git = write(0, "unix/git.sh");
+        let second_git = write(1, "unix/git.sh");
+        let third_git = write(2, "unix/git.sh");
+        write(0, "unix/curl.sh");
+        write(1, "debian/apt.sh");
+        write(0, "debian/nodejs.sh");
+        write(2, "unix/nodejs.sh");
+        let dirs: Vec<PathBuf> = roots.iter().map(|root| root.path().to_path_buf()).collect();
+
+        let scripts = collect_scripts(&dirs, &target(&["debian", "unix"])).unwrap();
+        let names: Vec<&str> = scripts.iter().map(|script| script.name.as_str()).collect();
+        assert_eq!(names, ["apt.sh", "curl.sh", "git.sh", "nodejs.sh"]);
+
+        let git = &scripts[2];
+        assert_eq!(git.path, third_git);
+        assert_eq!(git.root, dirs[2]);
+        assert_eq!(git.shadows, [first_git, second_git]);
+        assert_eq!(scripts[0].root, dirs[1]);
+        assert_eq!(scripts[1].root, dirs[0]);
+
+        // A later root does not shadow a more specific OS directory.
+        let nodejs = &scripts[3];
+        assert_eq!(nodejs.os_type, "debian");
+        assert_eq!(nodejs.root, dirs[0]);
+        assert!(nodejs.shadows.is_empty());
+        assert_eq!(nodejs.overrides, ["unix/nodejs.sh"]);
+    }
 }
@@ -0,0 +1,242 @@
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::scripts::models::ScriptInfo;
+
+/// A kind of environment the runner can find itself running in, which
+/// scripts may need to adapt to or avoid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Context {
+    /// Windows Subsystem for Linux.
+    Wsl,
+    /// Any container: Docker, Podman, LXC or another runtime.
+    Container,
+    Docker,
+    Podman,
+    Lxc,
+    /// A virtual machine.
+    Vm,
+    /// A Proxmox VE host, or a guest with Proxmox firmware.
+    Proxmox,
+    /// A continuous integration job.
+    Ci,
+}
+
+impl Context {
+    pub const ALL: [Context; 8] = [
+        Context::Wsl,
+        Context::Container,
+        Context::Docker,
+        Context::Podman,
+        Context::Lxc,
+        Context::Vm,
+        Context::Proxmox,
+        Context::Ci,
+    ];
+
+    /// Name used in `@skip-in` and `RUNNER_CONTEXT`, e.g. `container`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Context::Wsl => "wsl",
+            Context::Container => "container",
+            Context::Docker => "docker",
+            Context::Podman => "podman",
+            Context::Lxc => "lxc",
+            Context::Vm => "vm",
+            Context::Proxmox => "proxmox",
+            Context::Ci => "ci",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Context::ALL
+            .into_iter()
+            .find(|context| context.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Variable set to `1` or `0` for every script, e.g. `RUNNER_IN_CONTAINER`.
+    pub fn env_var(self) -> String {
+        format!("RUNNER_IN_{}", self.name().to_ascii_uppercase())
+    }
+}
+
+/// The contexts detected on this machine.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RuntimeContext {
+    pub detected: Vec<Context>,
+}
+
+impl RuntimeContext {
+    /// The contexts of the running machine, detected once per process.
+    pub fn current() -> &'static RuntimeContext {
+        static CURRENT: OnceLock<RuntimeContext> = OnceLock::new();
+        CURRENT.get_or_init(RuntimeContext::detect)
+    }
+
+    /// Inspects the environment, `/proc`, `/run` and DMI information.
+    pub fn detect() -> Self {
+        let mut detected = Vec::new();
+
+        let wsl = is_wsl();
+        if wsl {
+            detected.push(Context::Wsl);
+        }
+        let runtime = container_runtime();
+        if runtime.is_some() {
+            detected.push(Context::Container);
+        }
+        match runtime.as_deref() {
+            Some("docker") => detected.push(Context::Docker),
+            Some("podman") => detected.push(Context::Podman),
+            Some("lxc") | Some("lxc-libvirt") => detected.push(Context::Lxc),
+            _ => {}
+        }
+        // WSL and containers share the host's hypervisor flag.
+        if !wsl && runtime.is_none() && is_vm() {
+            detected.push(Context::Vm);
+        }
+        if is_proxmox() {
+            detected.push(Context::Proxmox);
+        }
+        if is_ci() {
+            detected.push(Context::Ci);
+        }
+
+        RuntimeContext { detected }
+    }
+
+    pub fn contains(&self, context: Context) -> bool {
+        self.detected.contains(&context)
+    }
+
+    /// Variables describing the contexts, set for every script:
+    /// `RUNNER_CONTEXT` lists the detected ones and `RUNNER_IN_<CONTEXT>` is
+    /// `1` or `0` for each known context.
+    pub fn environment(&self) -> Vec<(String, String)> {
+        let mut environment = vec![(String::from("RUNNER_CONTEXT"), self.names().join(","))];
+        for context in Context::ALL {
+            let value = if self.contains(context) { "1" } else { "0" };
+            environment.push((context.env_var(), String::from(value)));
+        }
+        environment
+    }
+
+    /// Why `script` must not run here, if one of its `@skip-in` contexts was
+    /// detected.
+    pub fn skip_reason(&self, script: &ScriptInfo) -> Option<String> {
+        script
+            .metadata
+            .skip_in
+            .iter()
+            .filter_map(|name| Context::from_name(name))
+            .find(|context| self.contains(*context))
+            .map(|context| format!("skipped in {}", context.name()))
+    }
+
+    fn names(&self) -> Vec<&'static str> {
+        self.detected.iter().map(|context| context.name()).collect()
+    }
+}
+
+impl fmt::Display for RuntimeContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.detected.is_empty() {
+            f.write_str("none")
+        } else {
+            f.write_str(&self.names().join(", "))
+        }
+    }
+}
+
+fn is_wsl() -> bool {
+    env::var_os("WSL_DISTRO_NAME").is_some()
+        || env::var_os("WSL_INTEROP").is_some()
+        || read_lowercase("/proc/sys/kernel/osrelease")
+            .is_some_and(|release| release.contains("microsoft") || release.contains("wsl"))
+}
+
+/// Name of the container runtime the runner is in, e.g. `docker` or `lxc`.
+fn container_runtime() -> Option<String> {
+    // Set by systemd, and by most runtimes for their init process.
+    if let Some(runtime) = read_lowercase("/run/systemd/container") {
+        return Some(runtime);
+    }
+    if let Some(runtime) = env::var("container").ok().filter(|value| !value.is_empty()) {
+        return Some(runtime.to_ascii_lowercase());
+    }
+    if Path::new("/.dockerenv").exists() {
+        return Some(String::from("docker"));
+    }
+    if Path::new("/run/.containerenv").exists() {
+        return Some(String::from("podman"));
+    }
+    if Path::new("/dev/lxd/sock").exists() {
+        return Some(String::from("lxc"));
+    }
+
+    let cgroup = read_lowercase("/proc/1/cgroup")?;
+    ["docker", "lxc", "kubepods", "containerd"]
+        .into_iter()
+        .find(|runtime| cgroup.contains(runtime))
+        .map(|runtime| match runtime {
+            "lxc" => String::from("lxc"),
+            _ => String::from("docker"),
+        })
+}
+
+fn is_vm() -> bool {
+    let vendor = dmi("sys_vendor");
+    let product = dmi("product_name");
+    [
+        "qemu",
+        "kvm",
+        "vmware",
+        "virtualbox",
+        "innotek",
+        "xen",
+        "bochs",
+        "parallels",
+    ]
+    .iter()
+    .any(|name| vendor.contains(name) || product.contains(name))
+        || product.contains("virtual machine")
+        || read_lowercase("/proc/cpuinfo").is_some_and(|cpuinfo| cpuinfo.contains(" hypervisor"))
+}
+
+fn is_proxmox() -> bool {
+    Path::new("/etc/pve").is_dir()
+        || ["bios_vendor", "sys_vendor", "product_name"]
+            .iter()
+            .any(|field| dmi(field).contains("proxmox"))
+}
+
+fn is_ci() -> bool {
+    let set = |name: &str| {
+        env::var(name).is_ok_and(|value| !value.is_empty() && !value.eq_ignore_ascii_case("false"))
+    };
+    [
+        "CI",
+        "GITHUB_ACTIONS",
+        "GITLAB_CI",
+        "BUILDKITE",
+        "JENKINS_URL",
+        "TF_BUILD",
+    ]
+    .iter()
+    .any(|name| set(name))
+}
+
+/// A DMI field such as `sys_vendor`, lowercased, or an empty string.
+fn dmi(field: &str) -> String {
+    read_lowercase(Path::new("/sys/class/dmi/id").join(field)).unwrap_or_default()
+}
+
+fn read_lowercase(path: impl AsRef<Path>) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|content| content.trim().to_ascii_lowercase())
+        .filter(|content| !content.is_empty())
+}
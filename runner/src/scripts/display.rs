@@ -1,14 +1,42 @@
+use crate::scripts::action::Action;
+use crate::scripts::check::Check;
+use crate::scripts::context::RuntimeContext;
+use crate::scripts::doctor::{Finding, Health};
+use crate::scripts::interpreter::resolve_interpreter;
+use crate::scripts::inventory::Inventory;
 use crate::scripts::models::ScriptInfo;
+use crate::scripts::outcome::{RunReport, ScriptStatus};
+use crate::scripts::plan::ExecutionPlan;
+use crate::scripts::runner::{script_environment, ExecutionOptions};
+use crate::scripts::state::{content_hash, StateStore};
 use anyhow::Result;
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
 /// Lists available scripts in various formats
-pub fn list_scripts(script_infos: &[ScriptInfo], format: &str) -> Result<()> {
+///
+/// With more than one scripts directory, the plain and table formats also show
+/// which directory each script comes from and which scripts it shadows.
+pub fn list_scripts(
+    script_infos: &[ScriptInfo],
+    format: &str,
+    scripts_dirs: &[PathBuf],
+) -> Result<()> {
     match format {
         "plain" => {
             for info in script_infos {
-                println!("{} [{}]", info.name, info.os_type);
+                let mut line = format!("{} [{}]", info.name, info.os_type);
+                if let Some(description) = &info.metadata.description {
+                    line.push_str(&format!(" - {}", description));
+                }
+                if scripts_dirs.len() > 1 {
+                    line.push_str(&format!(" (from {}", info.root.display()));
+                    for shadowed in &info.shadows {
+                        line.push_str(&format!(", shadows {}", shadowed.display()));
+                    }
+                    line.push(')');
+                }
+                println!("{}", line);
             }
         }
         "json" => {
@@ -16,66 +44,476 @@ pub fn list_scripts(script_infos: &[ScriptInfo], format: &str) -> Result<()> {
             println!("{}", json);
         }
         "csv" => write_scripts_to_csv(script_infos)?,
-        "table" => print_scripts_table(),
+        "table" => print_scripts_table(script_infos, scripts_dirs),
+        _ => return Err(anyhow::anyhow!("Unsupported format: {}", format)),
+    }
+
+    Ok(())
+}
+
+/// Prints the tool inventory of a machine in the same formats as
+/// [`list_scripts`]
+pub fn print_inventory(inventory: &Inventory, format: &str) -> Result<()> {
+    let machine = &inventory.machine;
+    match format {
+        "plain" => {
+            println!("{} ({}, {})", machine.hostname, machine.os, machine.arch);
+            for tool in &inventory.tools {
+                println!(
+                    "{} {} [{}] - {}",
+                    tool.name,
+                    tool.version.as_deref().unwrap_or(&tool.status),
+                    tool.kind,
+                    tool.script
+                );
+            }
+        }
+        "json" => println!("{}", serde_json::to_string_pretty(inventory)?),
+        "csv" => {
+            // Every row names the machine so reports of several machines can
+            // be concatenated.
+            let mut wtr = csv::WriterBuilder::new().from_writer(std::io::stdout());
+            wtr.write_record([
+                "Hostname", "OS", "Arch", "Script", "Kind", "Name", "Version", "Required",
+                "Source", "Status",
+            ])?;
+            for tool in &inventory.tools {
+                wtr.write_record([
+                    &machine.hostname,
+                    &machine.os,
+                    &machine.arch,
+                    &tool.script,
+                    &tool.kind.to_string(),
+                    &tool.name,
+                    tool.version.as_deref().unwrap_or_default(),
+                    tool.required.as_deref().unwrap_or_default(),
+                    tool.source.as_deref().unwrap_or_default(),
+                    &tool.status,
+                ])?;
+            }
+            wtr.flush()?;
+        }
+        "table" => {
+            let managers: Vec<&str> = inventory
+                .package_managers
+                .iter()
+                .map(|manager| manager.name())
+                .collect();
+            println!("Machine:          {}", machine.hostname);
+            println!("OS:               {} ({})", machine.os, machine.arch);
+            println!(
+                "Package managers: {}",
+                if managers.is_empty() {
+                    String::from("none")
+                } else {
+                    managers.join(", ")
+                }
+            );
+            println!();
+
+            let name_width = inventory
+                .tools
+                .iter()
+                .map(|tool| tool.name.len())
+                .chain(std::iter::once("Tool".len()))
+                .max()
+                .unwrap_or(0)
+                + 2;
+            let version_width = inventory
+                .tools
+                .iter()
+                .filter_map(|tool| tool.version.as_ref().map(String::len))
+                .chain(std::iter::once("Version".len()))
+                .max()
+                .unwrap_or(0)
+                + 2;
+            println!(
+                "{:<name_width$}{:<9}{:<version_width$}{:<16}Script",
+                "Tool", "Kind", "Version", "Status"
+            );
+            println!("{:-<width$}", "", width = name_width + version_width + 45);
+            for tool in &inventory.tools {
+                println!(
+                    "{:<name_width$}{:<9}{:<version_width$}{:<16}{}",
+                    tool.name,
+                    tool.kind.to_string(),
+                    tool.version.as_deref().unwrap_or("-"),
+                    tool.status,
+                    tool.script
+                );
+            }
+        }
         _ => return Err(anyhow::anyhow!("Unsupported format: {}", format)),
     }
 
     Ok(())
 }
 
+/// Prints the scripts of an execution plan in the order they will run
+pub fn print_execution_plan(scripts: &[ScriptInfo], plan: &ExecutionPlan) {
+    println!("Execution plan:");
+    for (step, &index) in plan.order.iter().enumerate() {
+        let script = &scripts[index];
+        if plan.is_auto_included(index) {
+            println!("  {:>2}. {} (required)", step + 1, script.display_name());
+        } else {
+            println!("  {:>2}. {}", step + 1, script.display_name());
+        }
+    }
+}
+
+/// Prints the status, exit code and duration of every script in a run
+pub fn print_run_summary(scripts: &[ScriptInfo], report: &RunReport) {
+    let name_width = report
+        .outcomes
+        .iter()
+        .map(|outcome| scripts[outcome.index].display_name().len())
+        .chain(std::iter::once("Script".len()))
+        .max()
+        .unwrap_or(0)
+        + 2;
+
+    println!();
+    println!(
+        "{:<name_width$}{:<11}{:<6}{:<10}Note",
+        "Script", "Status", "Exit", "Duration"
+    );
+    println!("{:-<width$}", "", width = name_width + 37);
+    for outcome in &report.outcomes {
+        let exit_code = outcome
+            .exit_code
+            .map_or_else(|| String::from("-"), |code| code.to_string());
+        let duration = if outcome.started_at.is_none() {
+            String::from("-")
+        } else {
+            format!("{:.1}s", outcome.duration.as_secs_f64())
+        };
+        let row = format!(
+            "{:<name_width$}{:<11}{:<6}{:<10}{}",
+            scripts[outcome.index].display_name(),
+            outcome.status.to_string(),
+            exit_code,
+            duration,
+            outcome.note.as_deref().unwrap_or_default()
+        );
+        println!("{}", row.trim_end());
+    }
+    let mut totals = format!("{} ok", report.count(ScriptStatus::Ok));
+    if report.count(ScriptStatus::Satisfied) > 0 {
+        totals.push_str(&format!(
+            ", {} already satisfied",
+            report.count(ScriptStatus::Satisfied)
+        ));
+    }
+    totals.push_str(&format!(
+        ", {} failed, {} skipped",
+        report.count(ScriptStatus::Failed),
+        report.count(ScriptStatus::Skipped)
+    ));
+    if report.was_cancelled() {
+        totals.push_str(&format!(
+            ", {} cancelled",
+            report.count(ScriptStatus::Cancelled)
+        ));
+    }
+    println!("\n{}", totals);
+}
+
+/// Prints the last recorded execution of every script and flags scripts
+/// whose contents changed since they were last applied successfully, or
+/// that can no longer be read
+pub fn print_status(scripts: &[ScriptInfo], store: &StateStore) -> Result<()> {
+    let name_width = scripts
+        .iter()
+        .map(|script| script.display_name().len())
+        .chain(std::iter::once("Script".len()))
+        .max()
+        .unwrap_or(0)
+        + 2;
+
+    println!(
+        "{:<name_width$}{:<22}{:<9}{:<6}State",
+        "Script", "Last Run (UTC)", "Status", "Exit"
+    );
+    println!("{:-<width$}", "", width = name_width + 50);
+    for script in scripts {
+        let Some(last_run) = store.last_run(script) else {
+            println!(
+                "{:<name_width$}{:<22}{:<9}{:<6}not applied",
+                script.display_name(),
+                "-",
+                "-",
+                "-"
+            );
+            continue;
+        };
+
+        let state = match store.last_success(script) {
+            Some(applied) if applied.action == Action::Remove => "removed",
+            Some(applied) => match (content_hash(&script.path), &applied.content_hash) {
+                (Err(_), _) => "unreadable",
+                (Ok(_), None) => "unknown",
+                (Ok(hash), Some(applied_hash)) if hash != *applied_hash => "changed since applied",
+                (Ok(_), Some(_)) => "up to date",
+            },
+            None => "not applied",
+        };
+        let exit_code = last_run
+            .exit_code
+            .map_or_else(|| String::from("-"), |code| code.to_string());
+        println!(
+            "{:<name_width$}{:<22}{:<9}{:<6}{}",
+            script.display_name(),
+            last_run
+                .started_at
+                .strftime("%Y-%m-%d %H:%M:%S")
+                .to_string(),
+            last_run.status.to_string(),
+            exit_code,
+            state
+        );
+    }
+
+    Ok(())
+}
+
+/// Prints whether each provided command was found, and what to do about
+/// those that were not.
+pub fn print_doctor_report(scripts: &[ScriptInfo], findings: &[Finding], store: &StateStore) {
+    let command_width = findings
+        .iter()
+        .map(|finding| finding.provide.command.len())
+        .chain(std::iter::once("Command".len()))
+        .max()
+        .unwrap_or(0)
+        + 2;
+
+    println!(
+        "{:<command_width$}{:<12}{:<12}{:<17}Script",
+        "Command", "Required", "Found", "Status"
+    );
+    println!("{:-<width$}", "", width = command_width + 61);
+    for finding in findings {
+        let script = &scripts[finding.script];
+        let required = finding
+            .provide
+            .constraint
+            .as_ref()
+            .map_or_else(|| String::from("-"), ToString::to_string);
+        let found = match (&finding.version, &finding.path) {
+            (Some(version), _) => version.to_string(),
+            (None, Some(_)) => String::from("yes"),
+            (None, None) => String::from("-"),
+        };
+        println!(
+            "{:<command_width$}{:<12}{:<12}{:<17}{}",
+            finding.provide.command,
+            required,
+            found,
+            finding.health.to_string(),
+            script.display_name()
+        );
+        match &finding.health {
+            Health::Broken(error) => println!("  {}", error),
+            Health::NotLoaded(dir) => println!("  found in {}", dir.display()),
+            _ => {}
+        }
+        if let Some(hint) = finding.remediation(script, store.is_installed(script)) {
+            println!("  -> {}", hint);
+        }
+    }
+
+    let healthy = findings
+        .iter()
+        .filter(|finding| finding.health.is_ok())
+        .count();
+    println!();
+    println!("{} of {} commands ok", healthy, findings.len());
+}
+
+/// Prints everything a run of `plan` would do, without executing it
+pub fn print_dry_run(
+    scripts: &[ScriptInfo],
+    plan: &ExecutionPlan,
+    temp_dir: &Path,
+    options: &ExecutionOptions,
+) {
+    println!("Dry run: no scripts will be executed");
+    println!("Working directory: {}", temp_dir.display());
+    print_execution_plan(scripts, plan);
+
+    for &index in &plan.order {
+        let script = &scripts[index];
+        let metadata = &script.metadata;
+        println!();
+        println!("{}", script.display_name());
+        println!("  path:        {}", script.path.display());
+        if !script.reason.is_empty() {
+            println!("  chosen:      {}", script.reason);
+        }
+        if let Some(reason) = RuntimeContext::current().skip_reason(script) {
+            println!("  context:     {}", reason);
+        }
+        let checks = Check::of(script);
+        if options.action.is_install() && !checks.is_empty() {
+            let checks: Vec<String> = checks.iter().map(ToString::to_string).collect();
+            let note = if options.force {
+                " (ignored with --force)"
+            } else {
+                ""
+            };
+            println!("  check:       {}{}", checks.join(", "), note);
+        }
+        let Some((action, action_script)) = options.action.resolve(script) else {
+            println!("  action:      {} NOT SUPPORTED", options.action);
+            continue;
+        };
+        if action != options.action {
+            println!("  action:      {} (no {} action)", action, options.action);
+        } else if !action.is_install() {
+            println!("  action:      {}", action);
+        }
+        let interpreter = resolve_interpreter(&action_script, &options.default_interpreter);
+        println!(
+            "  command:     {}",
+            interpreter.command_line(&action_script.path)
+        );
+        match interpreter.locate() {
+            Some(program) => println!(
+                "  interpreter: {} (from {})",
+                program.display(),
+                interpreter.source
+            ),
+            None => println!(
+                "  interpreter: {} NOT FOUND (from {})",
+                interpreter.program, interpreter.source
+            ),
+        }
+        println!("  environment:");
+        for (key, value) in options
+            .environment
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .chain(script_environment(script, temp_dir, action))
+        {
+            println!("    {}={}", key, value);
+        }
+        if !metadata.packages.is_empty() {
+            println!("  packages:    {}", metadata.packages.join(", "));
+        }
+        if !metadata.locks.is_empty() {
+            println!("  locks:       {}", metadata.locks.join(", "));
+        }
+        if metadata.needs_root {
+            println!("  needs root:  yes");
+        }
+        if metadata.interactive {
+            println!("  interactive: yes");
+        }
+        if let Some(timeout) = metadata.timeout {
+            println!("  timeout:     {}s", timeout);
+        }
+    }
+}
+
 fn write_scripts_to_csv(script_infos: &[ScriptInfo]) -> Result<(), anyhow::Error> {
     let mut wtr = csv::WriterBuilder::new().from_writer(std::io::stdout());
-    wtr.write_record(["Script Name", "OS Type", "Path"])?;
+    wtr.write_record([
+        "Script Name",
+        "OS Type",
+        "Path",
+        "Description",
+        "Tags",
+        "Requires",
+        "Needs Root",
+        "Interactive",
+        "Root",
+        "Shadows",
+    ])?;
     for info in script_infos {
+        let metadata = &info.metadata;
         wtr.write_record([
             &info.name,
             &info.os_type,
-            &info.path.to_string_lossy().to_string(),
+            info.path.to_string_lossy().as_ref(),
+            metadata.description.as_deref().unwrap_or_default(),
+            &metadata.tags.join(";"),
+            &metadata.requires.join(";"),
+            &metadata.needs_root.to_string(),
+            &metadata.interactive.to_string(),
+            info.root.to_string_lossy().as_ref(),
+            &info
+                .shadows
+                .iter()
+                .map(|path| path.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(";"),
         ])?;
     }
     wtr.flush()?;
     Ok(())
 }
 
-fn collect_scripts_by_os() -> HashMap<String, Vec<String>> {
-    let mut scripts_by_os = HashMap::new();
-    let scripts_dir = Path::new("../scripts");
-
-    if let Ok(entries) = std::fs::read_dir(scripts_dir) {
-        for entry in entries.filter_map(Result::ok) {
-            let path = entry.path();
-            if path.is_dir() {
-                let os_type = path.file_name().unwrap().to_string_lossy().to_string();
-                let mut scripts = Vec::new();
-
-                if let Ok(scripts_entries) = std::fs::read_dir(&path) {
-                    for script_entry in scripts_entries.filter_map(Result::ok) {
-                        if let Some(name) = script_entry.path().file_name() {
-                            scripts.push(name.to_string_lossy().to_string());
-                        }
-                    }
-                }
-
-                scripts.sort();
-                scripts_by_os.insert(os_type, scripts);
-            }
-        }
+/// Collected script names per OS directory. Each name maps to the index of
+/// the scripts directory that provides it and whether it shadows the same
+/// name in an earlier directory.
+fn collect_scripts_by_os(
+    scripts: &[ScriptInfo],
+    scripts_dirs: &[PathBuf],
+) -> HashMap<String, BTreeMap<String, (usize, bool)>> {
+    let mut scripts_by_os: HashMap<String, BTreeMap<String, (usize, bool)>> = HashMap::new();
+    for script in scripts {
+        let root_index = scripts_dirs
+            .iter()
+            .position(|dir| *dir == script.root)
+            .unwrap_or(0);
+        scripts_by_os
+            .entry(script.os_type.clone())
+            .or_default()
+            .insert(
+                script.name.clone(),
+                (root_index, !script.shadows.is_empty()),
+            );
     }
-
     scripts_by_os
 }
 
-fn print_scripts_table() {
-    let scripts_by_os = collect_scripts_by_os();
+fn print_scripts_table(scripts: &[ScriptInfo], scripts_dirs: &[PathBuf]) {
+    let scripts_by_os = collect_scripts_by_os(scripts, scripts_dirs);
     let mut os_types: Vec<&String> = scripts_by_os.keys().collect();
     os_types.sort();
 
+    // With several roots, mark each script with the root it comes from and
+    // whether it shadows a script of an earlier root.
+    let label = |name: &str, &(root_index, shadows): &(usize, bool)| {
+        if scripts_dirs.len() > 1 {
+            format!(
+                "{} [{}{}]",
+                name,
+                root_index + 1,
+                if shadows { "*" } else { "" }
+            )
+        } else {
+            name.to_string()
+        }
+    };
+    let columns: HashMap<&String, Vec<String>> = scripts_by_os
+        .iter()
+        .map(|(os_type, scripts)| {
+            let labels = scripts
+                .iter()
+                .map(|(name, origin)| label(name, origin))
+                .collect();
+            (os_type, labels)
+        })
+        .collect();
+
     let column_width = print_table_headers(&os_types);
-    let max_scripts = scripts_by_os.values().map(|v| v.len()).max().unwrap_or(0);
+    let max_scripts = columns.values().map(|v| v.len()).max().unwrap_or(0);
 
     for i in 0..max_scripts {
         for os_type in &os_types {
-            if let Some(scripts) = scripts_by_os.get(*os_type) {
+            if let Some(scripts) = columns.get(*os_type) {
                 if i < scripts.len() {
                     print!("{:<width$}", scripts[i], width = column_width);
                 } else {
@@ -87,6 +525,14 @@ fn print_scripts_table() {
         }
         println!();
     }
+
+    if scripts_dirs.len() > 1 {
+        println!();
+        for (root_index, scripts_dir) in scripts_dirs.iter().enumerate() {
+            println!("[{}] {}", root_index + 1, scripts_dir.display());
+        }
+        println!("* shadows the script of an earlier directory");
+    }
 }
 
 fn print_table_headers(os_types: &Vec<&String>) -> usize {
@@ -0,0 +1,192 @@
+use anyhow::{Context, Result};
+use log::debug;
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::scripts::interpreter::find_executable;
+use crate::scripts::models::ScriptInfo;
+use crate::scripts::process;
+use crate::scripts::provides::{Provide, Version};
+
+/// Time `<command> --version` gets before its version counts as unknown.
+pub const VERSION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How a command declared in `@provides` was found on this machine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Health {
+    /// On the `PATH`, in a version meeting the requirement.
+    Ok,
+    /// On the `PATH`, but its version could not be read to check the
+    /// requirement.
+    UnknownVersion,
+    /// On the `PATH` in a version not meeting the requirement.
+    Outdated,
+    /// On the `PATH`, but it could not be started.
+    Broken(String),
+    /// Not on the `PATH`, but in a directory a script added to the shell
+    /// settings, which the current shell has not loaded.
+    NotLoaded(PathBuf),
+    /// Nowhere to be found.
+    Missing,
+}
+
+impl Health {
+    /// Whether the command is usable as declared.
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Health::Ok | Health::UnknownVersion)
+    }
+}
+
+impl fmt::Display for Health {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Health::Ok => "ok",
+            Health::UnknownVersion => "version unknown",
+            Health::Outdated => "outdated",
+            Health::Broken(_) => "broken",
+            Health::NotLoaded(_) => "not on PATH",
+            Health::Missing => "missing",
+        })
+    }
+}
+
+/// The result of looking for one provided command.
+#[derive(Debug, Clone)]
+pub struct Finding {
+    /// Index of the script declaring the command.
+    pub script: usize,
+    pub provide: Provide,
+    /// Where the command was found.
+    pub path: Option<PathBuf>,
+    /// Version reported by `<command> --version`, if it was queried.
+    pub version: Option<Version>,
+    pub health: Health,
+}
+
+impl Finding {
+    /// What to do about a command that is not usable, given whether its
+    /// script has been installed.
+    pub fn remediation(&self, script: &ScriptInfo, installed: bool) -> Option<String> {
+        let name = &script.name;
+        Some(match &self.health {
+            Health::Ok | Health::UnknownVersion => return None,
+            Health::NotLoaded(_) => String::from(
+                "load the shell settings with `eval \"$(runner shellenv)\"` or open a new shell",
+            ),
+            _ if !installed => format!("install it with `runner run {}`", name),
+            Health::Outdated => format!("upgrade it with `runner upgrade {}`", name),
+            Health::Missing | Health::Broken(_) => format!("rerun `runner run --force {}`", name),
+        })
+    }
+}
+
+/// Looks for the commands declared in `@provides` by the scripts at
+/// `selections`, checking their versions unless `query_versions` is off.
+///
+/// Commands not on the `PATH` are also looked for in `shell_paths`, the
+/// directories scripts added to the shell settings.
+pub fn examine(
+    scripts: &[ScriptInfo],
+    selections: &[usize],
+    shell_paths: &[PathBuf],
+    query_versions: bool,
+) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for &index in selections {
+        for provide in Provide::of(&scripts[index]) {
+            findings.push(examine_command(index, provide, shell_paths, query_versions));
+        }
+    }
+    findings
+}
+
+fn examine_command(
+    script: usize,
+    provide: Provide,
+    shell_paths: &[PathBuf],
+    query_versions: bool,
+) -> Finding {
+    let mut finding = Finding {
+        script,
+        provide,
+        path: None,
+        version: None,
+        health: Health::Missing,
+    };
+
+    let Some(path) = find_executable(&finding.provide.command) else {
+        if let Some(dir) = shell_paths
+            .iter()
+            .find(|dir| dir.join(&finding.provide.command).is_file())
+        {
+            finding.health = Health::NotLoaded(dir.clone());
+        }
+        return finding;
+    };
+
+    finding.health = if !query_versions {
+        Health::Ok
+    } else {
+        match query_version(&path) {
+            Ok(version) => {
+                finding.version = version;
+                match (&finding.provide.constraint, &finding.version) {
+                    (None, _) => Health::Ok,
+                    (Some(_), None) => Health::UnknownVersion,
+                    (Some(constraint), Some(version)) if constraint.matches(version) => Health::Ok,
+                    (Some(_), Some(_)) => Health::Outdated,
+                }
+            }
+            Err(e) => Health::Broken(format!("{:#}", e)),
+        }
+    };
+    finding.path = Some(path);
+    finding
+}
+
+/// Runs `<program> --version` and picks the first dotted version out of its
+/// output. `None` if the program printed no version or did not finish within
+/// [`VERSION_TIMEOUT`]; an error only if it could not be started.
+pub fn query_version(program: &Path) -> Result<Option<Version>> {
+    // Output goes to a file rather than a pipe so a chatty program cannot
+    // block on a full pipe while we wait for it.
+    let mut output = tempfile::tempfile().context("Failed to create a temporary file")?;
+    let mut command = Command::new(program);
+    command
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stdout(output.try_clone()?)
+        .stderr(output.try_clone()?);
+    process::isolate(&mut command, false);
+
+    debug!("Running {} --version", program.display());
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("Failed to run {}", program.display()))?;
+    let deadline = Instant::now() + VERSION_TIMEOUT;
+    loop {
+        if child.try_wait()?.is_some() {
+            break;
+        }
+        if process::is_interrupted() || Instant::now() >= deadline {
+            process::terminate(&mut child, process::TERMINATION_GRACE_PERIOD)?;
+            debug!("{} --version did not finish", program.display());
+            return Ok(None);
+        }
+        thread::sleep(process::POLL_INTERVAL);
+    }
+
+    Ok(Version::find_in(&read_output(&mut output)?))
+}
+
+fn read_output(output: &mut File) -> Result<String> {
+    let mut bytes = Vec::new();
+    output.seek(SeekFrom::Start(0))?;
+    output.read_to_end(&mut bytes)?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
@@ -1,17 +1,20 @@
 use anyhow::Result;
 use include_dir::{include_dir, Dir};
 use log::{debug, info};
-use os_info::Type as OsType;
 use std::fs;
 use std::io::Write;
-use std::path::{Path, PathBuf};
+use std::path::Path;
 use tempfile::tempdir;
 
+use crate::scripts::interpreter::SCRIPT_EXTENSIONS;
+use crate::scripts::models::ScriptInfo;
+use crate::scripts::target::Target;
+
 // Include scripts directory in the binary
 static EMBEDDED_SCRIPTS: Dir = include_dir!("$CARGO_MANIFEST_DIR/../scripts");
 
 /// Extracts embedded scripts to a temporary directory and returns paths to them
-pub fn extract_embedded_scripts(os_type: OsType) -> Result<Vec<PathBuf>> {
+pub fn extract_embedded_scripts(target: &Target) -> Result<Vec<ScriptInfo>> {
     debug!("Extracting embedded scripts");
 
     let temp_dir = tempdir()?;
@@ -24,7 +27,7 @@ pub fn extract_embedded_scripts(os_type: OsType) -> Result<Vec<PathBuf>> {
     extract_directory(&EMBEDDED_SCRIPTS, &scripts_dir)?;
     info!("Extracted embedded scripts to: {}", scripts_dir.display());
 
-    let scripts = super::collector::collect_scripts(Some(&scripts_dir), os_type)?;
+    let scripts = super::collector::collect_scripts(&[scripts_dir], target)?;
 
     std::mem::forget(temp_dir);
 
@@ -40,7 +43,7 @@ fn extract_directory(dir: &Dir, target_dir: &Path) -> Result<()> {
         file_content.write_all(file.contents())?;
 
         if let Some(ext) = target_file.extension() {
-            if ext == "sh" || ext == "ps1" {
+            if SCRIPT_EXTENSIONS.iter().any(|supported| ext == *supported) {
                 #[cfg(unix)]
                 {
                     use std::os::unix::fs::PermissionsExt;
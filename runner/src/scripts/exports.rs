@@ -0,0 +1,270 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::scripts::models::ScriptInfo;
+use crate::scripts::shellenv::{parse_declarations, Declaration};
+
+/// Name of the directory inside a run's working directory holding the
+/// export files of each script.
+const EXPORTS_DIR: &str = ".runner-exports";
+
+/// Environment changes that scripts hand on to the scripts started after
+/// them in the same run, in the spirit of `GITHUB_ENV` and `GITHUB_PATH`.
+///
+/// A script appends `NAME=value` lines (or `NAME<<DELIMITER` blocks for
+/// multi-line values) to the file named by `$RUNNER_ENV`, and directories to
+/// the file named by `$RUNNER_PATH`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Exports {
+    pub variables: BTreeMap<String, String>,
+    /// Directories prepended to `PATH`, most recently exported first.
+    pub path: Vec<PathBuf>,
+}
+
+impl Exports {
+    pub fn is_empty(&self) -> bool {
+        self.variables.is_empty() && self.path.is_empty()
+    }
+
+    /// Reads the export files `script` wrote in `temp_dir`. Missing files
+    /// mean nothing was exported.
+    pub fn read(script: &ScriptInfo, temp_dir: &Path) -> Result<Self> {
+        let files = ExportFiles::new(script, temp_dir);
+        let mut exports = Exports::default();
+
+        if let Some(content) = read_optional(&files.env)? {
+            exports.variables = parse_env_file(&content)
+                .with_context(|| format!("Invalid export file {}", files.env.display()))?;
+        }
+        if let Some(content) = read_optional(&files.path)? {
+            for line in content
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+            {
+                exports.add_path(PathBuf::from(line));
+            }
+        }
+        Ok(exports)
+    }
+
+    /// Adds the exports of a later script, which take precedence.
+    pub fn merge(&mut self, later: &Exports) {
+        self.variables.extend(later.variables.clone());
+        for dir in later.path.iter().rev() {
+            self.add_path(dir.clone());
+        }
+    }
+
+    /// Variables to set for a script, with the exported directories in
+    /// front of `PATH`.
+    pub fn environment(&self) -> Result<Vec<(String, OsString)>> {
+        let mut environment: Vec<(String, OsString)> = self
+            .variables
+            .iter()
+            .filter(|(name, _)| *name != "PATH")
+            .map(|(name, value)| (name.clone(), OsString::from(value)))
+            .collect();
+
+        if !self.path.is_empty() || self.variables.contains_key("PATH") {
+            let base = match self.variables.get("PATH") {
+                Some(path) => OsString::from(path),
+                None => env::var_os("PATH").unwrap_or_default(),
+            };
+            let path = env::join_paths(self.path.iter().cloned().chain(env::split_paths(&base)))
+                .context("Exported PATH entry contains a path separator")?;
+            environment.push((String::from("PATH"), path));
+        }
+        Ok(environment)
+    }
+
+    fn add_path(&mut self, dir: PathBuf) {
+        self.path.retain(|existing| *existing != dir);
+        self.path.insert(0, dir);
+    }
+}
+
+/// The files a script writes its exports to.
+pub struct ExportFiles {
+    pub env: PathBuf,
+    pub path: PathBuf,
+    /// Shell settings to persist in the user's rc files, see
+    /// [`crate::scripts::shellenv`].
+    pub shell: PathBuf,
+}
+
+impl ExportFiles {
+    pub fn new(script: &ScriptInfo, temp_dir: &Path) -> Self {
+        let dir = temp_dir.join(EXPORTS_DIR);
+        ExportFiles {
+            env: dir.join(format!("{}.env", script.name)),
+            path: dir.join(format!("{}.path", script.name)),
+            shell: dir.join(format!("{}.shell", script.name)),
+        }
+    }
+
+    /// The declarations written to the `$RUNNER_SHELL` file, `None` if the
+    /// file does not exist.
+    pub fn shell_declarations(&self) -> Result<Option<Vec<Declaration>>> {
+        read_optional(&self.shell)?
+            .map(|content| {
+                parse_declarations(&content)
+                    .with_context(|| format!("Invalid shell settings in {}", self.shell.display()))
+            })
+            .transpose()
+    }
+
+    /// Creates the files empty, so scripts can simply append to them.
+    pub fn create(&self) -> Result<()> {
+        if let Some(dir) = self.env.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        for file in [&self.env, &self.path, &self.shell] {
+            fs::write(file, "").with_context(|| format!("Failed to create {}", file.display()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses `NAME=value` lines and `NAME<<DELIMITER` blocks.
+fn parse_env_file(content: &str) -> Result<BTreeMap<String, String>> {
+    let mut variables = BTreeMap::new();
+    let mut lines = content.lines().enumerate();
+
+    while let Some((index, line)) = lines.next() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let line_number = index + 1;
+
+        let heredoc = line
+            .find("<<")
+            .filter(|&start| line.find('=').is_none_or(|equals| start < equals));
+        let (name, value) = if let Some(start) = heredoc {
+            let (name, delimiter) = (&line[..start], &line[start + 2..]);
+            let delimiter = delimiter.trim();
+            let mut value = Vec::new();
+            loop {
+                match lines.next() {
+                    Some((_, line)) if line == delimiter => break,
+                    Some((_, line)) => value.push(line),
+                    None => {
+                        return Err(anyhow!(
+                            "line {}: missing closing delimiter `{}`",
+                            line_number,
+                            delimiter
+                        ))
+                    }
+                }
+            }
+            (name, value.join("\n"))
+        } else if let Some((name, value)) = line.split_once('=') {
+            (name, value.to_string())
+        } else {
+            return Err(anyhow!(
+                "line {}: expected NAME=value or NAME<<DELIMITER",
+                line_number
+            ));
+        };
+
+        let name = name.trim();
+        if !is_variable_name(name) {
+            return Err(anyhow!(
+                "line {}: invalid variable name `{}`",
+                line_number,
+                name
+            ));
+        }
+        variables.insert(name.to_string(), value);
+    }
+
+    Ok(variables)
+}
+
+fn is_variable_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn read_optional(path: &Path) -> Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> BTreeMap<String, String> {
+        parse_env_file(content).unwrap()
+    }
+
+    #[test]
+    fn parses_variables() {
+        let variables = parse("JAVA_HOME=/opt/java\n\n EMPTY =\nOPTS=-Xmx=2g a<<b\n");
+        assert_eq!(variables["JAVA_HOME"], "/opt/java");
+        assert_eq!(variables["EMPTY"], "");
+        assert_eq!(variables["OPTS"], "-Xmx=2g a<<b");
+        assert_eq!(variables.len(), 3);
+    }
+
+    #[test]
+    fn parses_heredocs() {
+        let variables =
+            parse("CERT<<EOF\nline one\n\nline=three\nEOF\nNEXT=1\nEMPTY<< END \nEND\n");
+        assert_eq!(variables["CERT"], "line one\n\nline=three");
+        assert_eq!(variables["NEXT"], "1");
+        assert_eq!(variables["EMPTY"], "");
+    }
+
+    #[test]
+    fn later_lines_override_earlier_ones() {
+        assert_eq!(parse("A=1\nA=2\n")["A"], "2");
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        let error = |content| parse_env_file(content).unwrap_err().to_string();
+        assert_eq!(
+            error("A=1\njust text\n"),
+            "line 2: expected NAME=value or NAME<<DELIMITER"
+        );
+        assert_eq!(error("1A=x"), "line 1: invalid variable name `1A`");
+        assert_eq!(error("MY-VAR=x"), "line 1: invalid variable name `MY-VAR`");
+        assert_eq!(
+            error("CERT<<EOF\nno end\n"),
+            "line 1: missing closing delimiter `EOF`"
+        );
+    }
+
+    #[test]
+    fn merges_later_exports_in_front() {
+        let mut exports = Exports {
+            variables: BTreeMap::from([(String::from("A"), String::from("1"))]),
+            path: vec![PathBuf::from("/a"), PathBuf::from("/b")],
+        };
+        exports.merge(&Exports {
+            variables: BTreeMap::from([(String::from("A"), String::from("2"))]),
+            path: vec![PathBuf::from("/c"), PathBuf::from("/b")],
+        });
+        assert_eq!(exports.variables["A"], "2");
+        assert_eq!(
+            exports.path,
+            [
+                PathBuf::from("/c"),
+                PathBuf::from("/b"),
+                PathBuf::from("/a")
+            ]
+        );
+    }
+}
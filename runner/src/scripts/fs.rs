@@ -1,11 +1,17 @@
 use log::debug;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 
-pub fn create_temp_dir() -> Result<PathBuf> {
-    let temp_dir = PathBuf::from("/tmp").join(format!("script-runner-{}", uuid::Uuid::new_v4()));
+/// Returns a fresh, not yet created, path under `root` for a script working
+/// directory.
+pub fn temp_dir_path(root: &Path) -> PathBuf {
+    root.join(format!("script-runner-{}", uuid::Uuid::new_v4()))
+}
+
+pub fn create_temp_dir(root: &Path) -> Result<PathBuf> {
+    let temp_dir = temp_dir_path(root);
     debug!("Creating temporary directory at: {}", temp_dir.display());
 
     fs::create_dir(&temp_dir).with_context(|| {
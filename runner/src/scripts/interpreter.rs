@@ -0,0 +1,296 @@
+use anyhow::{anyhow, Result};
+use std::env;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use crate::scripts::models::ScriptInfo;
+
+/// File extensions the collector treats as runnable scripts.
+pub const SCRIPT_EXTENSIONS: &[&str] = &["sh", "ps1", "py"];
+
+/// Interpreter for `.sh` scripts without a shebang when none is configured.
+pub const DEFAULT_INTERPRETER: &str = "bash";
+
+/// Program and leading arguments used to execute a script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interpreter {
+    pub program: String,
+    pub args: Vec<String>,
+    /// Where the interpreter choice came from, for diagnostics.
+    pub source: InterpreterSource,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterpreterSource {
+    Metadata,
+    Shebang,
+    Extension,
+    Default,
+}
+
+impl fmt::Display for InterpreterSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            InterpreterSource::Metadata => "@interpreter",
+            InterpreterSource::Shebang => "shebang",
+            InterpreterSource::Extension => "file extension",
+            InterpreterSource::Default => "default",
+        };
+        f.write_str(label)
+    }
+}
+
+impl Interpreter {
+    fn new(command_line: &str, source: InterpreterSource) -> Option<Self> {
+        let mut parts = command_line.split_whitespace().map(String::from);
+        let program = parts.next()?;
+        let mut interpreter = Interpreter {
+            program,
+            args: parts.collect(),
+            source,
+        };
+        if interpreter.args.is_empty() && interpreter.is_powershell() {
+            interpreter.args = powershell_args();
+        }
+        Some(interpreter)
+    }
+
+    fn is_powershell(&self) -> bool {
+        Path::new(&self.program)
+            .file_stem()
+            .is_some_and(|stem| stem == "pwsh" || stem == "powershell")
+    }
+
+    /// Full command line for running `script`, for display purposes.
+    pub fn command_line(&self, script: &Path) -> String {
+        let mut parts = vec![self.program.clone()];
+        parts.extend(self.args.iter().cloned());
+        parts.push(script.display().to_string());
+        parts.join(" ")
+    }
+
+    /// Locates the interpreter program, returning its full path.
+    pub fn locate(&self) -> Option<PathBuf> {
+        find_executable(&self.program)
+    }
+}
+
+/// Determines how to run `script`.
+///
+/// In order of precedence: an `@interpreter` metadata override, the script's
+/// shebang, its file extension (`.ps1` runs with `pwsh`, `.py` with python),
+/// and finally `default`, which also runs `.sh` scripts.
+pub fn resolve_interpreter(script: &ScriptInfo, default: &str) -> Interpreter {
+    if let Some(interpreter) = script
+        .metadata
+        .interpreter
+        .as_deref()
+        .and_then(|command_line| Interpreter::new(command_line, InterpreterSource::Metadata))
+    {
+        return interpreter;
+    }
+
+    if let Some(interpreter) =
+        read_shebang(&script.path).and_then(|shebang| interpreter_from_shebang(&shebang))
+    {
+        return interpreter;
+    }
+
+    let extension = script
+        .path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase());
+    let by_extension = match extension.as_deref() {
+        Some("ps1") => Some("pwsh"),
+        Some("py") if cfg!(windows) => Some("python"),
+        Some("py") => Some("python3"),
+        _ => None,
+    };
+    by_extension
+        .and_then(|program| Interpreter::new(program, InterpreterSource::Extension))
+        .or_else(|| Interpreter::new(default, InterpreterSource::Default))
+        .unwrap_or_else(|| Interpreter {
+            program: String::from(DEFAULT_INTERPRETER),
+            args: Vec::new(),
+            source: InterpreterSource::Default,
+        })
+}
+
+/// Resolves the interpreter for `script` and checks that it is installed.
+pub fn require_interpreter(script: &ScriptInfo, default: &str) -> Result<(Interpreter, PathBuf)> {
+    let interpreter = resolve_interpreter(script, default);
+    match interpreter.locate() {
+        Some(path) => Ok((interpreter, path)),
+        None => Err(anyhow!(
+            "Interpreter '{}' for {} (from {}) was not found; install it or set `@interpreter` in the script header",
+            interpreter.program,
+            script.display_name(),
+            interpreter.source
+        )),
+    }
+}
+
+fn read_shebang(path: &Path) -> Option<String> {
+    let file = File::open(path).ok()?;
+    let mut first_line = String::new();
+    BufReader::new(file).read_line(&mut first_line).ok()?;
+    first_line
+        .trim()
+        .strip_prefix("#!")
+        .map(|shebang| shebang.trim().to_string())
+}
+
+/// Turns a shebang into an interpreter, unwrapping `/usr/bin/env`.
+///
+/// Absolute interpreter paths that do not exist on this machine (e.g.
+/// `/bin/bash` on Windows) fall back to looking up the program by name.
+fn interpreter_from_shebang(shebang: &str) -> Option<Interpreter> {
+    let mut parts = shebang.split_whitespace();
+    let program = parts.next()?;
+    let rest: Vec<&str> = parts.collect();
+
+    let command_line = if Path::new(program)
+        .file_name()
+        .is_some_and(|name| name == "env")
+    {
+        let rest: Vec<&str> = rest.into_iter().filter(|arg| *arg != "-S").collect();
+        rest.join(" ")
+    } else if Path::new(program).is_absolute() && !Path::new(program).exists() {
+        let name = Path::new(program)
+            .file_name()?
+            .to_string_lossy()
+            .to_string();
+        std::iter::once(name.as_str())
+            .chain(rest)
+            .collect::<Vec<_>>()
+            .join(" ")
+    } else {
+        shebang.to_string()
+    };
+
+    Interpreter::new(&command_line, InterpreterSource::Shebang)
+}
+
+fn powershell_args() -> Vec<String> {
+    let mut args = vec![String::from("-NoProfile")];
+    if cfg!(windows) {
+        args.extend([String::from("-ExecutionPolicy"), String::from("Bypass")]);
+    }
+    args.push(String::from("-File"));
+    args
+}
+
+/// Finds `program` on the `PATH`, or checks it directly if it contains a path.
+pub fn find_executable(program: &str) -> Option<PathBuf> {
+    let candidate = Path::new(program);
+    if candidate.components().count() > 1 {
+        return candidate.is_file().then(|| candidate.to_path_buf());
+    }
+
+    let extensions: Vec<String> = if cfg!(windows) {
+        env::var("PATHEXT")
+            .unwrap_or_else(|_| String::from(".EXE;.CMD;.BAT;.COM"))
+            .split(';')
+            .map(String::from)
+            .chain(std::iter::once(String::new()))
+            .collect()
+    } else {
+        vec![String::new()]
+    };
+
+    env::split_paths(&env::var_os("PATH")?).find_map(|dir| {
+        extensions.iter().find_map(|extension| {
+            let path = dir.join(format!("{}{}", program, extension));
+            path.is_file().then_some(path)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn shebang(shebang: &str) -> (String, Vec<String>) {
+        let interpreter = interpreter_from_shebang(shebang).unwrap();
+        assert_eq!(interpreter.source, InterpreterSource::Shebang);
+        (interpreter.program, interpreter.args)
+    }
+
+    fn script(path: &Path, interpreter: Option<&str>) -> ScriptInfo {
+        let mut script = ScriptInfo::for_test("unix", "script");
+        script.path = path.to_path_buf();
+        script.metadata.interpreter = interpreter.map(String::from);
+        script
+    }
+
+    #[test]
+    fn unwraps_env_shebangs() {
+        assert_eq!(
+            shebang("/usr/bin/env python3"),
+            (String::from("python3"), vec![])
+        );
+        assert_eq!(
+            shebang("/usr/bin/env -S bash -eu"),
+            (String::from("bash"), vec![String::from("-eu")])
+        );
+        assert!(interpreter_from_shebang("/usr/bin/env").is_none());
+    }
+
+    #[test]
+    fn looks_up_missing_absolute_interpreters_by_name() {
+        assert_eq!(
+            shebang("/nonexistent/bin/zsh -f"),
+            (String::from("zsh"), vec![String::from("-f")])
+        );
+        assert_eq!(
+            shebang("bash -x"),
+            (String::from("bash"), vec![String::from("-x")])
+        );
+    }
+
+    #[test]
+    fn passes_file_arguments_to_powershell() {
+        let (program, args) = shebang("/usr/bin/env pwsh");
+        assert_eq!(program, "pwsh");
+        assert_eq!(args, powershell_args());
+        assert_eq!(args.last().map(String::as_str), Some("-File"));
+    }
+
+    #[test]
+    fn resolves_interpreters_in_order_of_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        let with_shebang = dir.path().join("a.sh");
+        fs::write(&with_shebang, "#!/usr/bin/env -S bash -e\necho hi\n").unwrap();
+        let without_shebang = dir.path().join("b.sh");
+        fs::write(&without_shebang, "echo hi\n").unwrap();
+        let python = dir.path().join("c.py");
+        fs::write(&python, "print('hi')\n").unwrap();
+
+        let resolve = |path: &Path, interpreter: Option<&str>| {
+            let interpreter = resolve_interpreter(&script(path, interpreter), "zsh -e");
+            (interpreter.program, interpreter.source)
+        };
+        assert_eq!(
+            resolve(&with_shebang, Some("sh -x")),
+            (String::from("sh"), InterpreterSource::Metadata)
+        );
+        assert_eq!(
+            resolve(&with_shebang, None),
+            (String::from("bash"), InterpreterSource::Shebang)
+        );
+        assert_eq!(
+            resolve(&python, None),
+            (
+                String::from(if cfg!(windows) { "python" } else { "python3" }),
+                InterpreterSource::Extension
+            )
+        );
+        assert_eq!(
+            resolve(&without_shebang, None),
+            (String::from("zsh"), InterpreterSource::Default)
+        );
+    }
+}
@@ -0,0 +1,331 @@
+use anyhow::{bail, Context, Result};
+use jiff::Timestamp;
+use log::{debug, warn};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use crate::scripts::doctor::examine;
+use crate::scripts::interpreter::find_executable;
+use crate::scripts::models::ScriptInfo;
+
+/// A package manager whose installed packages can be listed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageManager {
+    Dpkg,
+    Apk,
+    Brew,
+    Choco,
+}
+
+impl PackageManager {
+    pub const ALL: [PackageManager; 4] = [
+        PackageManager::Dpkg,
+        PackageManager::Apk,
+        PackageManager::Brew,
+        PackageManager::Choco,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PackageManager::Dpkg => "dpkg",
+            PackageManager::Apk => "apk",
+            PackageManager::Brew => "brew",
+            PackageManager::Choco => "choco",
+        }
+    }
+
+    /// The commands listing the installed packages with their versions.
+    fn queries(self) -> &'static [&'static [&'static str]] {
+        match self {
+            PackageManager::Dpkg => &[&[
+                "dpkg-query",
+                "-W",
+                "-f",
+                "${db:Status-Abbrev}\t${Package}\t${Version}\n",
+            ]],
+            PackageManager::Apk => &[&["apk", "info", "-v"]],
+            PackageManager::Brew => &[
+                &["brew", "list", "--formula", "--versions"],
+                &["brew", "list", "--cask", "--versions"],
+            ],
+            // Chocolatey 2 only lists local packages
+            PackageManager::Choco => &[&["choco", "list", "--limit-output"]],
+        }
+    }
+
+    /// The package managers available on this machine.
+    pub fn available() -> Vec<PackageManager> {
+        PackageManager::ALL
+            .into_iter()
+            .filter(|manager| find_executable(manager.queries()[0][0]).is_some())
+            .collect()
+    }
+
+    /// The installed packages and their versions.
+    pub fn installed(self) -> Result<BTreeMap<String, String>> {
+        let mut packages = BTreeMap::new();
+        for query in self.queries() {
+            debug!("Listing {} packages with `{}`", self, query.join(" "));
+            let output = Command::new(query[0])
+                .args(&query[1..])
+                .stdin(Stdio::null())
+                .output()
+                .with_context(|| format!("Failed to run {}", query[0]))?;
+            if !output.status.success() {
+                bail!(
+                    "`{}` failed: {}",
+                    query.join(" "),
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+            }
+            packages.extend(self.parse(&String::from_utf8_lossy(&output.stdout)));
+        }
+        Ok(packages)
+    }
+
+    /// Parses the output of the package listing into names and versions.
+    pub fn parse(self, output: &str) -> BTreeMap<String, String> {
+        output
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .filter_map(|line| match self {
+                // `ii \tcurl\t7.88.1-10+deb12u8`; removed packages whose
+                // configuration was kept are listed as `rc`
+                PackageManager::Dpkg => {
+                    let (status, package) = line.split_once('\t')?;
+                    if !status.starts_with("ii") {
+                        return None;
+                    }
+                    package.split_once('\t')
+                }
+                // `curl-8.5.0-r0`: the version starts at the second to last `-`
+                PackageManager::Apk => {
+                    let release = line.rfind('-')?;
+                    let version = line[..release].rfind('-')?;
+                    Some((&line[..version], &line[version + 1..]))
+                }
+                // `neovim 0.10.1 0.10.0`: the newest version is listed last
+                PackageManager::Brew => {
+                    let (name, versions) = line.split_once(' ')?;
+                    Some((name, versions.split_whitespace().last()?))
+                }
+                // `git|2.45.2`
+                PackageManager::Choco => line.split_once('|'),
+            })
+            .map(|(name, version)| (name.to_string(), version.trim().to_string()))
+            .collect()
+    }
+}
+
+impl fmt::Display for PackageManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The machine an inventory was taken on.
+#[derive(Debug, Clone, Serialize)]
+pub struct Machine {
+    pub hostname: String,
+    pub os: String,
+    pub arch: String,
+}
+
+impl Machine {
+    pub fn current() -> Self {
+        Machine {
+            hostname: hostname(),
+            os: os_info::get().to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+        }
+    }
+}
+
+/// What kind of tool an inventory entry describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolKind {
+    /// A command declared in `@provides`.
+    Command,
+    /// A package declared in `@packages`.
+    Package,
+}
+
+impl fmt::Display for ToolKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ToolKind::Command => "command",
+            ToolKind::Package => "package",
+        })
+    }
+}
+
+/// One tool a script installs and what was found of it.
+#[derive(Debug, Clone, Serialize)]
+pub struct Tool {
+    pub name: String,
+    pub kind: ToolKind,
+    /// Display name of the script declaring the tool.
+    pub script: String,
+    pub version: Option<String>,
+    /// Version requirement from `@provides`.
+    pub required: Option<String>,
+    /// Where the version came from: the command's path or the package
+    /// manager.
+    pub source: Option<String>,
+    pub status: String,
+}
+
+/// The tools the scripts declare and their versions on one machine.
+#[derive(Debug, Clone, Serialize)]
+pub struct Inventory {
+    pub machine: Machine,
+    pub taken_at: Timestamp,
+    pub package_managers: Vec<PackageManager>,
+    pub tools: Vec<Tool>,
+}
+
+/// Takes the inventory of the commands declared in `@provides` and the
+/// packages declared in `@packages` by the scripts at `selections`.
+///
+/// Command versions are read from `<command> --version`; package versions
+/// from every available package manager. A package manager that cannot be
+/// queried is reported and left out.
+pub fn take_inventory(
+    scripts: &[ScriptInfo],
+    selections: &[usize],
+    shell_paths: &[PathBuf],
+) -> Inventory {
+    let mut package_managers = Vec::new();
+    let mut packages: Vec<(PackageManager, BTreeMap<String, String>)> = Vec::new();
+    for manager in PackageManager::available() {
+        match manager.installed() {
+            Ok(installed) => {
+                debug!("{} lists {} installed packages", manager, installed.len());
+                package_managers.push(manager);
+                packages.push((manager, installed));
+            }
+            Err(e) => warn!("Failed to list {} packages: {:#}", manager, e),
+        }
+    }
+
+    let mut tools: Vec<Tool> = examine(scripts, selections, shell_paths, true)
+        .into_iter()
+        .map(|finding| Tool {
+            name: finding.provide.command.clone(),
+            kind: ToolKind::Command,
+            script: scripts[finding.script].display_name(),
+            version: finding.version.as_ref().map(ToString::to_string),
+            required: finding.provide.constraint.as_ref().map(ToString::to_string),
+            source: finding.path.as_ref().map(|path| path.display().to_string()),
+            status: finding.health.to_string(),
+        })
+        .collect();
+
+    for &index in selections {
+        for package in &scripts[index].metadata.packages {
+            let found = packages.iter().find_map(|(manager, installed)| {
+                installed
+                    .get(package)
+                    .map(|version| (manager.to_string(), version.clone()))
+            });
+            tools.push(Tool {
+                name: package.clone(),
+                kind: ToolKind::Package,
+                script: scripts[index].display_name(),
+                status: String::from(if found.is_some() {
+                    "installed"
+                } else {
+                    "not installed"
+                }),
+                source: found.as_ref().map(|(manager, _)| manager.clone()),
+                version: found.map(|(_, version)| version),
+                required: None,
+            });
+        }
+    }
+
+    Inventory {
+        machine: Machine::current(),
+        taken_at: Timestamp::now(),
+        package_managers,
+        tools,
+    }
+}
+
+#[cfg(unix)]
+fn hostname() -> String {
+    let mut buffer = [0u8; 256];
+    // SAFETY: the buffer outlives the call and its length is passed along.
+    let result = unsafe { libc::gethostname(buffer.as_mut_ptr().cast(), buffer.len()) };
+    if result != 0 {
+        return String::from("unknown");
+    }
+    let end = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
+    String::from_utf8_lossy(&buffer[..end]).into_owned()
+}
+
+#[cfg(not(unix))]
+fn hostname() -> String {
+    std::env::var("COMPUTERNAME").unwrap_or_else(|_| String::from("unknown"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(manager: PackageManager, output: &str) -> Vec<(String, String)> {
+        manager.parse(output).into_iter().collect()
+    }
+
+    fn packages(entries: &[(&str, &str)]) -> Vec<(String, String)> {
+        entries
+            .iter()
+            .map(|(name, version)| (name.to_string(), version.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_dpkg_output() {
+        let output = "ii \tcurl\t7.88.1-10+deb12u8\nrc \told-tool\t1.0\n\nii \tlibc6\t2.36-9\n";
+        assert_eq!(
+            parse(PackageManager::Dpkg, output),
+            packages(&[("curl", "7.88.1-10+deb12u8"), ("libc6", "2.36-9")])
+        );
+    }
+
+    #[test]
+    fn parses_apk_output() {
+        let output = "curl-8.5.0-r0\nca-certificates-bundle-20240226-r0\nbroken\n";
+        assert_eq!(
+            parse(PackageManager::Apk, output),
+            packages(&[
+                ("ca-certificates-bundle", "20240226-r0"),
+                ("curl", "8.5.0-r0")
+            ])
+        );
+    }
+
+    #[test]
+    fn parses_brew_output() {
+        let output = "neovim 0.10.0 0.10.1\ngit 2.45.2\nempty\n";
+        assert_eq!(
+            parse(PackageManager::Brew, output),
+            packages(&[("git", "2.45.2"), ("neovim", "0.10.1")])
+        );
+    }
+
+    #[test]
+    fn parses_choco_output() {
+        let output = "git|2.45.2\r\nnodejs|22.4.1\r\nChocolatey v2.3.0\r\n";
+        assert_eq!(
+            parse(PackageManager::Choco, output),
+            packages(&[("git", "2.45.2"), ("nodejs", "22.4.1")])
+        );
+    }
+}
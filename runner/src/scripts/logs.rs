@@ -0,0 +1,209 @@
+use anyhow::{anyhow, Context, Result};
+use jiff::Timestamp;
+use log::{debug, warn};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::scripts::models::ScriptInfo;
+
+/// Name of the log holding the timestamped output of every script in a run.
+pub const COMBINED_LOG: &str = "combined.log";
+
+/// Number of run log directories kept when none is configured.
+pub const DEFAULT_KEPT_RUNS: usize = 10;
+
+/// Log directory of a single run: `<log dir>/<run id>/`.
+///
+/// Holds one transcript per script, at `<os dir>/<script name>.log`, and a
+/// combined log of all output with timestamps.
+#[derive(Debug, Clone)]
+pub struct RunLogs {
+    dir: PathBuf,
+    combined: Arc<Mutex<File>>,
+}
+
+impl RunLogs {
+    /// Creates the log directory for `run_id` under `root`, appending to
+    /// existing logs when a run is resumed.
+    pub fn create(root: &Path, run_id: &str) -> Result<Self> {
+        let dir = root.join(run_id);
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create log directory: {}", dir.display()))?;
+        let combined = open_append(&dir.join(COMBINED_LOG))?;
+        Ok(RunLogs {
+            dir,
+            combined: Arc::new(Mutex::new(combined)),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Opens the transcript of `script`, writing a header line to it.
+    pub fn transcript(&self, script: &ScriptInfo) -> Result<Transcript> {
+        let path = self
+            .dir
+            .join(&script.os_type)
+            .join(format!("{}.log", script.name));
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = open_append(&path)?;
+        writeln!(
+            file,
+            "==> {} started at {}",
+            script.display_name(),
+            Timestamp::now()
+        )?;
+        Ok(Transcript {
+            name: script.name.clone(),
+            file: Mutex::new(file),
+            combined: Arc::clone(&self.combined),
+        })
+    }
+}
+
+/// Output capture for one running script.
+#[derive(Debug)]
+pub struct Transcript {
+    name: String,
+    file: Mutex<File>,
+    combined: Arc<Mutex<File>>,
+}
+
+impl Transcript {
+    /// Appends an output line to the script transcript and the combined log.
+    pub fn write_line(&self, line: &str, is_stderr: bool) {
+        if let Ok(mut file) = self.file.lock() {
+            writeln!(file, "{}", line).ok();
+        }
+        if let Ok(mut combined) = self.combined.lock() {
+            let stream = if is_stderr { "err" } else { "out" };
+            writeln!(
+                combined,
+                "{} [{}] {}: {}",
+                Timestamp::now(),
+                self.name,
+                stream,
+                line
+            )
+            .ok();
+        }
+    }
+
+    /// Writes a closing line with the script's result.
+    pub fn finish(&self, result: &str) {
+        if let Ok(mut file) = self.file.lock() {
+            writeln!(file, "==> {} at {}", result, Timestamp::now()).ok();
+        }
+    }
+}
+
+/// IDs of the runs that have logs under `root`, oldest first.
+///
+/// Run IDs start with their creation time, so sorting them by name sorts
+/// them chronologically.
+pub fn list_logged_runs(root: &Path) -> Result<Vec<String>> {
+    if !root.exists() {
+        return Ok(Vec::new());
+    }
+    let mut runs: Vec<String> = fs::read_dir(root)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    runs.sort();
+    Ok(runs)
+}
+
+/// Removes the log directories of all but the `keep` most recent runs.
+pub fn prune_logs(root: &Path, keep: usize) -> Result<()> {
+    let runs = list_logged_runs(root)?;
+    let excess = runs.len().saturating_sub(keep);
+    for run_id in &runs[..excess] {
+        let dir = root.join(run_id);
+        debug!("Removing old run logs: {}", dir.display());
+        if let Err(e) = fs::remove_dir_all(&dir) {
+            warn!("Failed to remove old logs {}: {}", dir.display(), e);
+        }
+    }
+    Ok(())
+}
+
+/// Transcript files of a run, relative to its log directory.
+pub fn list_transcripts(root: &Path, run_id: &str) -> Result<Vec<PathBuf>> {
+    let dir = run_log_dir(root, run_id)?;
+    let mut transcripts: Vec<PathBuf> = walkdir::WalkDir::new(&dir)
+        .min_depth(2)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "log"))
+        .filter_map(|entry| entry.path().strip_prefix(&dir).ok().map(Path::to_path_buf))
+        .collect();
+    transcripts.sort();
+    Ok(transcripts)
+}
+
+/// Finds the log of `script` in a run. Accepts `combined`, a script name
+/// (`apt.sh`), its stem (`apt`) or an OS qualified name (`debian/apt.sh`).
+pub fn find_transcript(root: &Path, run_id: &str, script: &str) -> Result<PathBuf> {
+    let dir = run_log_dir(root, run_id)?;
+    if script == "combined" || script == COMBINED_LOG {
+        return Ok(dir.join(COMBINED_LOG));
+    }
+
+    let candidates: Vec<PathBuf> = list_transcripts(root, run_id)?
+        .into_iter()
+        .filter(|transcript| {
+            let name = transcript.with_extension("");
+            let file_name = name.file_name().map(|n| n.to_string_lossy().to_string());
+            let stem = name.file_stem().map(|n| n.to_string_lossy().to_string());
+            let qualified = name.to_string_lossy().replace('\\', "/");
+            file_name.as_deref() == Some(script)
+                || stem.as_deref() == Some(script)
+                || qualified == script
+        })
+        .collect();
+
+    match candidates.as_slice() {
+        [transcript] => Ok(dir.join(transcript)),
+        [] => Err(anyhow!("No log for '{}' in run {}", script, run_id)),
+        _ => Err(anyhow!(
+            "'{}' matches several logs in run {}: {}",
+            script,
+            run_id,
+            candidates
+                .iter()
+                .map(|c| c.with_extension("").display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// Log directory of `run_id`; `latest` refers to the most recent run.
+pub fn run_log_dir(root: &Path, run_id: &str) -> Result<PathBuf> {
+    let run_id = if run_id == "latest" {
+        list_logged_runs(root)?
+            .pop()
+            .ok_or_else(|| anyhow!("No run logs recorded yet"))?
+    } else {
+        run_id.to_string()
+    };
+    let dir = root.join(&run_id);
+    if !dir.is_dir() {
+        return Err(anyhow!("No logs for run '{}'", run_id));
+    }
+    Ok(dir)
+}
+
+fn open_append(path: &Path) -> Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open log file: {}", path.display()))
+}
@@ -0,0 +1,398 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::scripts::action::Action;
+use crate::scripts::context::Context;
+use crate::scripts::provides::Provide;
+
+/// Structured metadata declared in a script's leading comment header.
+///
+/// Directives take the form `# @key: value` and must appear in the comment
+/// block at the top of the script (after an optional shebang), e.g.
+///
+/// ```text
+/// #!/bin/bash
+/// # @description: Install Node.js through nvm
+/// # @tags: dev, javascript
+/// # @requires: apt.sh
+/// # @needs-root
+/// # @lock: apt
+/// # @timeout: 30m
+/// # @skip-in: container, wsl
+/// # @check: command -v node
+/// # @actions: remove
+/// # @provides: node>=20, npm
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScriptMetadata {
+    /// Short human readable summary of what the script does.
+    pub description: Option<String>,
+    /// Free-form labels used for grouping and selection.
+    pub tags: Vec<String>,
+    /// Names of other scripts that must run before this one.
+    pub requires: Vec<String>,
+    /// Whether the script escalates privileges (e.g. through `sudo`).
+    pub needs_root: bool,
+    /// Whether the script prompts for user input while running.
+    pub interactive: bool,
+    /// Exclusive resources held while running, e.g. the `apt` package lock.
+    /// Scripts sharing a lock never run concurrently.
+    pub locks: Vec<String>,
+    /// System packages the script installs or modifies.
+    pub packages: Vec<String>,
+    /// Command line used to run the script, overriding shebang and extension.
+    pub interpreter: Option<String>,
+    /// Time limit in seconds after which the script is stopped.
+    pub timeout: Option<u64>,
+    /// Contexts (see [`Context`]) in which the script is skipped.
+    #[serde(default)]
+    pub skip_in: Vec<String>,
+    /// Shell command that succeeds when the script does not need to run.
+    #[serde(default)]
+    pub check: Option<String>,
+    /// Actions besides installing (see [`Action`]) that the script performs
+    /// itself, depending on `RUNNER_ACTION`.
+    #[serde(default)]
+    pub actions: Vec<String>,
+    /// Commands the script makes available, optionally with a version
+    /// requirement (see [`Provide`]), e.g. `nvim>=0.10`.
+    #[serde(default)]
+    pub provides: Vec<String>,
+}
+
+/// A problem found while parsing a script's metadata header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataDiagnostic {
+    /// 1-based line number of the offending header line.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for MetadataDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Reads the script at `path` and parses its metadata header.
+///
+/// Unreadable scripts yield empty metadata alongside a diagnostic so that a
+/// single broken file does not prevent the rest from being collected.
+pub fn read_metadata(path: &Path) -> (ScriptMetadata, Vec<MetadataDiagnostic>) {
+    match fs::read_to_string(path) {
+        Ok(content) => parse_metadata(&content),
+        Err(e) => (
+            ScriptMetadata::default(),
+            vec![MetadataDiagnostic {
+                line: 0,
+                message: format!("failed to read script: {}", e),
+            }],
+        ),
+    }
+}
+
+/// Parses the metadata header of a script's contents.
+///
+/// Parsing stops at the first line that is neither blank nor a comment, so
+/// `@` directives further down in the script body are ignored.
+pub fn parse_metadata(content: &str) -> (ScriptMetadata, Vec<MetadataDiagnostic>) {
+    let mut metadata = ScriptMetadata::default();
+    let mut diagnostics = Vec::new();
+    let mut seen = Vec::new();
+
+    for (index, raw_line) in content.lines().enumerate() {
+        let line_number = index + 1;
+        let line = raw_line.trim();
+
+        if line.is_empty() || (index == 0 && line.starts_with("#!")) {
+            continue;
+        }
+        let Some(comment) = line.strip_prefix('#') else {
+            break;
+        };
+        let Some(directive) = comment.trim_start().strip_prefix('@') else {
+            continue;
+        };
+
+        let mut report = |message: String| {
+            diagnostics.push(MetadataDiagnostic {
+                line: line_number,
+                message,
+            })
+        };
+
+        let (key, value) = split_directive(directive);
+        if key.is_empty() {
+            report(String::from("directive is missing a key after `@`"));
+            continue;
+        }
+
+        let repeatable = matches!(
+            key,
+            "tags" | "requires" | "lock" | "packages" | "skip-in" | "actions" | "provides"
+        );
+        if !repeatable && seen.contains(&key) {
+            report(format!("duplicate `@{}` directive", key));
+            continue;
+        }
+        seen.push(key);
+
+        match key {
+            "description" => {
+                if value.is_empty() {
+                    report(String::from("`@description` requires a value"));
+                } else {
+                    metadata.description = Some(value.to_string());
+                }
+            }
+            "tags" => metadata.tags.extend(parse_list(value)),
+            "requires" => metadata.requires.extend(parse_list(value)),
+            "lock" => metadata.locks.extend(parse_list(value)),
+            "interpreter" => {
+                if value.is_empty() {
+                    report(String::from("`@interpreter` requires a command"));
+                } else {
+                    metadata.interpreter = Some(value.to_string());
+                }
+            }
+            "packages" => metadata.packages.extend(parse_list(value)),
+            "check" => {
+                if value.is_empty() {
+                    report(String::from("`@check` requires a command"));
+                } else {
+                    metadata.check = Some(value.to_string());
+                }
+            }
+            "timeout" => match parse_duration(value) {
+                Ok(timeout) => metadata.timeout = Some(timeout.as_secs()),
+                Err(e) => report(format!("invalid `@timeout`: {}", e)),
+            },
+            "skip-in" => {
+                for name in parse_list(value) {
+                    match Context::from_name(&name) {
+                        Some(context) => metadata.skip_in.push(context.name().to_string()),
+                        None => report(format!(
+                            "unknown context `{}` in `@skip-in`, expected one of: {}",
+                            name,
+                            Context::ALL.map(Context::name).join(", ")
+                        )),
+                    }
+                }
+            }
+            "actions" => {
+                for name in parse_list(value) {
+                    match Action::from_name(&name).filter(|action| !action.is_install()) {
+                        Some(action) => metadata.actions.push(action.name().to_string()),
+                        None => report(format!(
+                            "unknown action `{}` in `@actions`, expected one of: {}",
+                            name,
+                            Action::ALL
+                                .into_iter()
+                                .filter(|action| !action.is_install())
+                                .map(Action::name)
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        )),
+                    }
+                }
+            }
+            "provides" => {
+                // Only commas separate entries, so `nvim >= 0.10` stays whole.
+                for item in value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                {
+                    match Provide::parse(item) {
+                        Ok(provide) => metadata.provides.push(provide.to_string()),
+                        Err(e) => report(format!("invalid `@provides` entry: {}", e)),
+                    }
+                }
+            }
+            "needs-root" => match parse_flag(value) {
+                Some(flag) => metadata.needs_root = flag,
+                None => report(invalid_flag(key, value)),
+            },
+            "interactive" => match parse_flag(value) {
+                Some(flag) => metadata.interactive = flag,
+                None => report(invalid_flag(key, value)),
+            },
+            _ => report(format!("unknown metadata directive `@{}`", key)),
+        }
+    }
+
+    (metadata, diagnostics)
+}
+
+/// Splits `key: value` (or `key value`) into its trimmed parts.
+fn split_directive(directive: &str) -> (&str, &str) {
+    let key_end = directive
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+        .unwrap_or(directive.len());
+    let (key, rest) = directive.split_at(key_end);
+    let rest = rest.trim_start();
+    let value = rest.strip_prefix(':').unwrap_or(rest);
+    (key, value.trim())
+}
+
+/// Splits a comma or whitespace separated list, dropping empty entries.
+fn parse_list(value: &str) -> impl Iterator<Item = String> + '_ {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+}
+
+/// Parses a duration such as `90`, `90s`, `30m` or `2h`; plain numbers are seconds.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let (number, unit) = value.split_at(
+        value
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(value.len()),
+    );
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("expected a duration like 90s, 30m or 2h, found `{}`", value))?;
+    let multiplier = match unit.trim() {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        other => return Err(format!("unknown duration unit `{}`", other)),
+    };
+    if number == 0 {
+        return Err(String::from("duration must be greater than zero"));
+    }
+    number
+        .checked_mul(multiplier)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("duration `{}` is too long", value))
+}
+
+/// Parses a boolean flag; a bare directive without a value means `true`.
+fn parse_flag(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "" | "true" | "yes" => Some(true),
+        "false" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+fn invalid_flag(key: &str, value: &str) -> String {
+    format!(
+        "`@{}` expects true/false (or no value), found `{}`",
+        key, value
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_header_directives() {
+        let content = "\
+#!/bin/bash
+# Installs Node.js
+# @description: Install Node.js through nvm
+# @tags: dev, javascript
+# @tags web
+# @requires: apt.sh
+# @needs-root
+# @interactive: no
+# @lock: apt
+# @timeout: 30m
+# @skip-in: container, wsl
+# @check: command -v node
+# @actions: remove
+# @provides: node >= 20, npm
+
+echo '# @description: not part of the header'
+";
+        let (metadata, diagnostics) = parse_metadata(content);
+        assert_eq!(diagnostics, []);
+        assert_eq!(
+            metadata,
+            ScriptMetadata {
+                description: Some(String::from("Install Node.js through nvm")),
+                tags: vec![
+                    String::from("dev"),
+                    String::from("javascript"),
+                    String::from("web")
+                ],
+                requires: vec![String::from("apt.sh")],
+                needs_root: true,
+                interactive: false,
+                locks: vec![String::from("apt")],
+                packages: Vec::new(),
+                interpreter: None,
+                timeout: Some(30 * 60),
+                skip_in: vec![String::from("container"), String::from("wsl")],
+                check: Some(String::from("command -v node")),
+                actions: vec![String::from("remove")],
+                provides: vec![String::from("node>=20"), String::from("npm")],
+            }
+        );
+    }
+
+    #[test]
+    fn stops_at_the_script_body() {
+        let (metadata, diagnostics) = parse_metadata("set -e\n# @description: too late\n");
+        assert_eq!(metadata, ScriptMetadata::default());
+        assert_eq!(diagnostics, []);
+    }
+
+    #[test]
+    fn reports_malformed_directives_with_their_line() {
+        let content = "\
+#!/bin/sh
+# @
+# @check
+# @description: first
+# @description: second
+# @needs-root: maybe
+# @timeout: 5d
+# @skip-in: moon
+# @actions: install
+# @provides: nvim >= latest
+# @colour: blue
+";
+        let (metadata, diagnostics) = parse_metadata(content);
+        let lines: Vec<usize> = diagnostics.iter().map(|d| d.line).collect();
+        assert_eq!(lines, [2, 3, 5, 6, 7, 8, 9, 10, 11]);
+        assert_eq!(diagnostics[2].message, "duplicate `@description` directive");
+        assert_eq!(
+            diagnostics[8].message,
+            "unknown metadata directive `@colour`"
+        );
+        // Valid directives around the broken ones still apply.
+        assert_eq!(metadata.description.as_deref(), Some("first"));
+        assert!(!metadata.needs_root);
+        assert_eq!(metadata.timeout, None);
+        assert!(metadata.skip_in.is_empty());
+        assert!(metadata.actions.is_empty());
+        assert!(metadata.provides.is_empty());
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration(" 30m "), Ok(Duration::from_secs(30 * 60)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(2 * 60 * 60)));
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        for value in ["", "m", "1.5h", "-5", "0", "0s", "5d", "10 minutes"] {
+            assert!(parse_duration(value).is_err(), "accepted `{}`", value);
+        }
+        assert_eq!(
+            parse_duration(&format!("{}h", u64::MAX / 60)),
+            Err(format!("duration `{}h` is too long", u64::MAX / 60))
+        );
+    }
+}
@@ -1,6 +1,24 @@
+pub mod action;
+pub mod check;
 pub mod collector;
+pub mod context;
 pub mod display;
+pub mod doctor;
 pub mod embedded;
+pub mod exports;
 pub mod fs;
+pub mod interpreter;
+pub mod inventory;
+pub mod logs;
+pub mod metadata;
 pub mod models;
+pub mod outcome;
+pub mod plan;
+pub mod process;
+pub mod provides;
 pub mod runner;
+pub mod runs;
+pub mod selector;
+pub mod shellenv;
+pub mod state;
+pub mod target;
@@ -1,12 +1,163 @@
+use log::warn;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-/// Information about a script including its name, path, and OS.
+use crate::scripts::metadata::{read_metadata, ScriptMetadata};
+
+/// Information about a script including its name, path, OS and header metadata.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScriptInfo {
     pub name: String,
     pub path: PathBuf,
     pub os_type: String,
+    #[serde(default)]
+    pub metadata: ScriptMetadata,
+    /// Scripts directory the script was collected from.
+    #[serde(default)]
+    pub root: PathBuf,
+    /// Scripts with the same name and OS directory in earlier roots that this
+    /// one replaces.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shadows: Vec<PathBuf>,
+    /// Qualified names (`unix/nodejs.sh`) of the scripts with the same name in
+    /// later OS directories of the fallback chain that this one replaces.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub overrides: Vec<String>,
+    /// Why this script was chosen over its version and architecture variants.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub reason: String,
+    /// Companion scripts found next to this one.
+    #[serde(default, skip_serializing_if = "Companions::is_empty")]
+    pub companions: Companions,
+}
+
+/// Actions a companion script can perform for the script it accompanies,
+/// used as the second to last part of its file name (`nodejs.check.sh`).
+pub const COMPANION_ACTIONS: &[&str] = &["check", "remove", "upgrade"];
+
+/// Scripts accompanying a script in the same directory, named after it with
+/// an action before the extension: `nodejs.check.sh` for `nodejs.sh`.
+///
+/// Companions of an architecture variant such as `nodejs.arm64.sh` may be
+/// named after the variant (`nodejs.arm64.check.sh`) or the logical script
+/// (`nodejs.check.sh`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Companions {
+    /// Exits successfully when what the script sets up is already in place.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub check: Option<PathBuf>,
+    /// Undoes what the script installed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remove: Option<PathBuf>,
+    /// Brings what the script installed up to date.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upgrade: Option<PathBuf>,
+}
+
+impl Companions {
+    /// Looks up the companions of the script at `path` whose logical name is `name`.
+    pub fn find(path: &Path, name: &str) -> Self {
+        Companions {
+            check: find_companion(path, name, "check"),
+            remove: find_companion(path, name, "remove"),
+            upgrade: find_companion(path, name, "upgrade"),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.check.is_none() && self.remove.is_none() && self.upgrade.is_none()
+    }
+}
+
+/// Whether `path` is a companion script rather than a script of its own.
+pub fn is_companion_file(path: &Path) -> bool {
+    let Some(file_name) = path.file_name().map(|name| name.to_string_lossy()) else {
+        return false;
+    };
+    let parts: Vec<&str> = file_name.split('.').collect();
+    parts.len() >= 3 && COMPANION_ACTIONS.contains(&parts[parts.len() - 2])
+}
+
+fn find_companion(path: &Path, name: &str, action: &str) -> Option<PathBuf> {
+    let dir = path.parent()?;
+    let extension = path.extension()?.to_string_lossy();
+    let stems = [
+        path.file_stem()
+            .map(|stem| stem.to_string_lossy().to_string()),
+        Path::new(name)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string()),
+    ];
+    stems
+        .into_iter()
+        .flatten()
+        .map(|stem| dir.join(format!("{}.{}.{}", stem, action, extension)))
+        .find(|companion| companion.is_file())
+}
+
+impl ScriptInfo {
+    /// Builds the script info for `path`, parsing its metadata header.
+    ///
+    /// Malformed header lines are reported as warnings and otherwise ignored.
+    pub fn from_path(path: &Path) -> Self {
+        let name = path.file_name().map_or_else(
+            || String::from("<unknown>"),
+            |name| name.to_string_lossy().to_string(),
+        );
+
+        let os_type = path
+            .parent()
+            .and_then(|parent| parent.file_name())
+            .map(|dir_name| dir_name.to_string_lossy().to_string())
+            .filter(|dir_name| dir_name != "scripts")
+            .unwrap_or_else(|| String::from("common"));
+
+        let (metadata, diagnostics) = read_metadata(path);
+        for diagnostic in &diagnostics {
+            warn!("Invalid metadata in {}: {}", path.display(), diagnostic);
+        }
+
+        let companions = Companions::find(path, &name);
+        ScriptInfo {
+            name,
+            path: path.to_path_buf(),
+            os_type,
+            metadata,
+            root: PathBuf::new(),
+            shadows: Vec::new(),
+            overrides: Vec::new(),
+            reason: String::new(),
+            companions,
+        }
+    }
+
+    /// Name shown to users, e.g. `nodejs.sh [debian]`.
+    pub fn display_name(&self) -> String {
+        if self.os_type == "common" {
+            self.name.clone()
+        } else {
+            format!("{} [{}]", self.name, self.os_type)
+        }
+    }
+}
+
+#[cfg(test)]
+impl ScriptInfo {
+    /// A script `name` in the OS directory `os_type` with empty metadata,
+    /// without reading anything from disk.
+    pub(crate) fn for_test(os_type: &str, name: &str) -> Self {
+        ScriptInfo {
+            name: name.to_string(),
+            path: PathBuf::from(os_type).join(name),
+            os_type: os_type.to_string(),
+            metadata: ScriptMetadata::default(),
+            root: PathBuf::new(),
+            shadows: Vec::new(),
+            overrides: Vec::new(),
+            reason: String::new(),
+            companions: Companions::default(),
+        }
+    }
 }
 
 /// Trait for converting a collection of paths into a collection of names.
@@ -43,29 +194,6 @@ impl PathNames for Vec<PathBuf> {
     }
 
     fn into_script_infos(self) -> Vec<ScriptInfo> {
-        self.iter()
-            .map(|p| {
-                let file_name = p.file_name().map_or_else(
-                    || String::from("<unknown>"),
-                    |name| name.to_string_lossy().to_string(),
-                );
-
-                let os_type = p
-                    .parent()
-                    .and_then(|parent| parent.file_name())
-                    .map(|dir_name| dir_name.to_string_lossy().to_string())
-                    .unwrap_or_else(|| String::from("common"));
-
-                ScriptInfo {
-                    name: file_name,
-                    path: p.clone(),
-                    os_type: if os_type == "scripts" {
-                        String::from("common")
-                    } else {
-                        os_type
-                    },
-                }
-            })
-            .collect()
+        self.iter().map(|p| ScriptInfo::from_path(p)).collect()
     }
 }
@@ -0,0 +1,129 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+use crate::scripts::exports::Exports;
+use crate::scripts::shellenv::Declaration;
+
+/// Final state of a script within a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScriptStatus {
+    Ok,
+    /// Not run because its checks found it already applied.
+    Satisfied,
+    Failed,
+    Skipped,
+    /// Stopped or never started because the run was interrupted.
+    Cancelled,
+}
+
+impl ScriptStatus {
+    /// Whether the script needs nothing more in its run: it succeeded, was
+    /// already satisfied or was deliberately skipped.
+    pub fn is_complete(self) -> bool {
+        matches!(
+            self,
+            ScriptStatus::Ok | ScriptStatus::Satisfied | ScriptStatus::Skipped
+        )
+    }
+}
+
+impl fmt::Display for ScriptStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            ScriptStatus::Ok => "ok",
+            ScriptStatus::Satisfied => "satisfied",
+            ScriptStatus::Failed => "failed",
+            ScriptStatus::Skipped => "skipped",
+            ScriptStatus::Cancelled => "cancelled",
+        };
+        f.write_str(label)
+    }
+}
+
+/// What happened to a single script of an execution plan.
+#[derive(Debug, Clone)]
+pub struct ScriptOutcome {
+    /// Index of the script in the collected scripts.
+    pub index: usize,
+    pub status: ScriptStatus,
+    /// Exit code of the script process, if it ran to completion.
+    pub exit_code: Option<i32>,
+    /// When the script was started, `None` if it never ran.
+    pub started_at: Option<Timestamp>,
+    pub duration: Duration,
+    /// Why the script failed or was skipped.
+    pub note: Option<String>,
+    /// Environment changes the script exported, if it succeeded.
+    pub exports: Option<Exports>,
+    /// Settings the script declared for the user's shells, if it succeeded.
+    pub shell: Option<Vec<Declaration>>,
+}
+
+impl ScriptOutcome {
+    /// A script that was not run because its checks passed.
+    pub fn satisfied(
+        index: usize,
+        started_at: Timestamp,
+        duration: Duration,
+        note: impl Into<String>,
+    ) -> Self {
+        ScriptOutcome {
+            index,
+            status: ScriptStatus::Satisfied,
+            exit_code: None,
+            started_at: Some(started_at),
+            duration,
+            note: Some(note.into()),
+            exports: None,
+            shell: None,
+        }
+    }
+
+    pub fn skipped(index: usize, note: impl Into<String>) -> Self {
+        ScriptOutcome::not_started(index, ScriptStatus::Skipped, note)
+    }
+
+    /// A script that was never started because the run was interrupted.
+    pub fn cancelled(index: usize, note: impl Into<String>) -> Self {
+        ScriptOutcome::not_started(index, ScriptStatus::Cancelled, note)
+    }
+
+    fn not_started(index: usize, status: ScriptStatus, note: impl Into<String>) -> Self {
+        ScriptOutcome {
+            index,
+            status,
+            exit_code: None,
+            started_at: None,
+            duration: Duration::ZERO,
+            note: Some(note.into()),
+            exports: None,
+            shell: None,
+        }
+    }
+}
+
+/// Outcomes of every script in a run, in execution plan order.
+#[derive(Debug, Clone, Default)]
+pub struct RunReport {
+    pub outcomes: Vec<ScriptOutcome>,
+}
+
+impl RunReport {
+    pub fn count(&self, status: ScriptStatus) -> usize {
+        self.outcomes
+            .iter()
+            .filter(|outcome| outcome.status == status)
+            .count()
+    }
+
+    pub fn has_failures(&self) -> bool {
+        self.count(ScriptStatus::Failed) > 0
+    }
+
+    pub fn was_cancelled(&self) -> bool {
+        self.count(ScriptStatus::Cancelled) > 0
+    }
+}
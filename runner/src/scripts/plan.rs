@@ -0,0 +1,336 @@
+use anyhow::{anyhow, Result};
+use log::debug;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::scripts::models::ScriptInfo;
+
+/// Ordered list of scripts to execute, with prerequisites resolved.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecutionPlan {
+    /// Indices into the collected scripts, in the order they must run.
+    pub order: Vec<usize>,
+    /// Indices of scripts that were not selected but are required by one that was.
+    pub auto_included: Vec<usize>,
+    /// Resolved `@requires` edges for every script in the plan.
+    pub dependencies: BTreeMap<usize, Vec<usize>>,
+}
+
+impl ExecutionPlan {
+    /// Whether the script at `index` was pulled in as a dependency.
+    pub fn is_auto_included(&self, index: usize) -> bool {
+        self.auto_included.contains(&index)
+    }
+
+    /// Removes scripts that no longer need to run, e.g. ones that already
+    /// succeeded in an earlier attempt. Dependencies on them count as met.
+    pub fn without(mut self, done: &[usize]) -> Self {
+        self.order.retain(|index| !done.contains(index));
+        self.auto_included.retain(|index| !done.contains(index));
+        self.dependencies.retain(|index, _| !done.contains(index));
+        for dependencies in self.dependencies.values_mut() {
+            dependencies.retain(|index| !done.contains(index));
+        }
+        self
+    }
+
+    /// Scripts that must complete before the script at `index` can start.
+    pub fn dependencies_of(&self, index: usize) -> &[usize] {
+        self.dependencies.get(&index).map_or(&[], Vec::as_slice)
+    }
+
+    /// Whether the script at `index` requires `dependency`, directly or
+    /// through other scripts of the plan.
+    pub fn requires(&self, index: usize, dependency: usize) -> bool {
+        let mut stack = self.dependencies_of(index).to_vec();
+        let mut seen = Vec::new();
+        while let Some(next) = stack.pop() {
+            if next == dependency {
+                return true;
+            }
+            if !seen.contains(&next) {
+                seen.push(next);
+                stack.extend_from_slice(self.dependencies_of(next));
+            }
+        }
+        false
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum VisitState {
+    Unvisited,
+    Visiting,
+    Done,
+}
+
+/// Resolves the selected scripts into a dependency-respecting execution order.
+///
+/// Scripts named in `@requires` are added to the plan when missing from the
+/// selection. Selected scripts otherwise keep their relative order, so a plan
+/// without dependencies runs exactly as selected.
+pub fn resolve_execution_plan(
+    scripts: &[ScriptInfo],
+    selections: &[usize],
+) -> Result<ExecutionPlan> {
+    let mut states = vec![VisitState::Unvisited; scripts.len()];
+    let mut path = Vec::new();
+    let mut plan = ExecutionPlan::default();
+
+    for &index in selections {
+        visit(index, scripts, &mut states, &mut path, &mut plan)?;
+    }
+
+    plan.auto_included = plan
+        .order
+        .iter()
+        .copied()
+        .filter(|index| !selections.contains(index))
+        .collect();
+    debug!("Resolved execution order: {:?}", plan.order);
+    Ok(plan)
+}
+
+/// Orders the selected scripts for removal: each one after the selected
+/// scripts that require it, so dependents are removed first. Unlike
+/// installing, required scripts are not added to the plan.
+pub fn resolve_removal_plan(scripts: &[ScriptInfo], selections: &[usize]) -> Result<ExecutionPlan> {
+    let install = resolve_execution_plan(scripts, selections)?;
+    let mut plan = ExecutionPlan {
+        order: install
+            .order
+            .iter()
+            .rev()
+            .copied()
+            .filter(|index| selections.contains(index))
+            .collect(),
+        ..ExecutionPlan::default()
+    };
+    for &index in &plan.order {
+        let dependents = plan
+            .order
+            .iter()
+            .copied()
+            .filter(|&other| install.requires(other, index))
+            .collect();
+        plan.dependencies.insert(index, dependents);
+    }
+    debug!("Resolved removal order: {:?}", plan.order);
+    Ok(plan)
+}
+
+/// Scripts that require the script at `index`, directly or through others.
+///
+/// Walks `@requires` directly instead of resolving each script's plan, so a
+/// dependent is still found when another of its requirements is missing or
+/// part of a cycle.
+pub fn find_dependents(scripts: &[ScriptInfo], index: usize) -> Vec<usize> {
+    (0..scripts.len())
+        .filter(|&other| other != index && requires_transitively(scripts, other, index))
+        .collect()
+}
+
+fn requires_transitively(scripts: &[ScriptInfo], script: usize, dependency: usize) -> bool {
+    let mut stack = vec![script];
+    let mut seen = vec![script];
+    while let Some(next) = stack.pop() {
+        for name in &scripts[next].metadata.requires {
+            let Some(required) = find_dependency(scripts, &scripts[next], name) else {
+                continue;
+            };
+            if required == dependency {
+                return true;
+            }
+            if !seen.contains(&required) {
+                seen.push(required);
+                stack.push(required);
+            }
+        }
+    }
+    false
+}
+
+/// Finds the script referenced by `name`, by file name or stem.
+///
+/// Names are those of the logical script, so `flutter` also finds the
+/// architecture variant `debian/flutter.amd64.sh`.
+///
+/// A script in the same directory as the dependent wins over one elsewhere,
+/// so `debian/flutter.sh` requiring `git` picks `debian/git.sh` over `unix/git.sh`.
+fn find_dependency(scripts: &[ScriptInfo], dependent: &ScriptInfo, name: &str) -> Option<usize> {
+    let matches = |script: &ScriptInfo| {
+        script.name == name
+            || Path::new(&script.name)
+                .file_stem()
+                .is_some_and(|stem| stem.to_string_lossy() == name)
+    };
+
+    scripts
+        .iter()
+        .position(|script| matches(script) && script.os_type == dependent.os_type)
+        .or_else(|| scripts.iter().position(matches))
+}
+
+fn visit(
+    index: usize,
+    scripts: &[ScriptInfo],
+    states: &mut [VisitState],
+    path: &mut Vec<usize>,
+    plan: &mut ExecutionPlan,
+) -> Result<()> {
+    match states[index] {
+        VisitState::Done => return Ok(()),
+        VisitState::Visiting => {
+            let start = path.iter().position(|&i| i == index).unwrap_or(0);
+            let cycle: Vec<String> = path[start..]
+                .iter()
+                .chain(std::iter::once(&index))
+                .map(|&i| scripts[i].display_name())
+                .collect();
+            return Err(anyhow!("Dependency cycle detected: {}", cycle.join(" -> ")));
+        }
+        VisitState::Unvisited => {}
+    }
+
+    states[index] = VisitState::Visiting;
+    path.push(index);
+    let script = &scripts[index];
+    let mut dependencies = Vec::new();
+    for name in &script.metadata.requires {
+        let dependency = find_dependency(scripts, script, name).ok_or_else(|| {
+            anyhow!(
+                "Script {} requires '{}', which is not available for this OS",
+                script.display_name(),
+                name
+            )
+        })?;
+        visit(dependency, scripts, states, path, plan)?;
+        dependencies.push(dependency);
+    }
+    path.pop();
+    states[index] = VisitState::Done;
+    plan.order.push(index);
+    plan.dependencies.insert(index, dependencies);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn script(os_type: &str, name: &str, requires: &[&str]) -> ScriptInfo {
+        let mut script = ScriptInfo::for_test(os_type, name);
+        script.metadata.requires = requires.iter().map(|name| name.to_string()).collect();
+        script
+    }
+
+    #[test]
+    fn keeps_the_selection_order_without_dependencies() {
+        let scripts = [
+            script("debian", "a.sh", &[]),
+            script("debian", "b.sh", &[]),
+            script("debian", "c.sh", &[]),
+        ];
+        let plan = resolve_execution_plan(&scripts, &[2, 0, 1]).unwrap();
+        assert_eq!(plan.order, [2, 0, 1]);
+        assert!(plan.auto_included.is_empty());
+    }
+
+    #[test]
+    fn runs_prerequisites_first_and_includes_missing_ones() {
+        let scripts = [
+            script("debian", "apt.sh", &[]),
+            script("debian", "git.sh", &["apt"]),
+            script("debian", "flutter.sh", &["git.sh", "apt.sh"]),
+        ];
+        let plan = resolve_execution_plan(&scripts, &[2, 1]).unwrap();
+        assert_eq!(plan.order, [0, 1, 2]);
+        assert_eq!(plan.auto_included, [0]);
+        assert!(plan.is_auto_included(0));
+        assert_eq!(plan.dependencies_of(2), [1, 0]);
+        assert!(plan.requires(2, 0));
+        assert!(!plan.requires(0, 2));
+    }
+
+    #[test]
+    fn prefers_prerequisites_from_the_same_directory() {
+        let scripts = [
+            script("unix", "git.sh", &[]),
+            script("debian", "git.sh", &[]),
+            script("debian", "flutter.sh", &["git"]),
+        ];
+        let plan = resolve_execution_plan(&scripts, &[2]).unwrap();
+        assert_eq!(plan.order, [1, 2]);
+    }
+
+    #[test]
+    fn finds_prerequisites_by_their_logical_name() {
+        let mut flutter = script("debian", "flutter.sh", &[]);
+        flutter.path = PathBuf::from("debian/flutter.amd64.sh");
+        let scripts = [flutter, script("debian", "android.sh", &["flutter"])];
+        let plan = resolve_execution_plan(&scripts, &[1]).unwrap();
+        assert_eq!(plan.order, [0, 1]);
+        assert_eq!(find_dependents(&scripts, 0), [1]);
+    }
+
+    #[test]
+    fn removes_dependents_before_their_prerequisites() {
+        let scripts = [
+            script("debian", "apt.sh", &[]),
+            script("debian", "git.sh", &["apt"]),
+            script("debian", "flutter.sh", &["git"]),
+        ];
+        let plan = resolve_removal_plan(&scripts, &[0, 2, 1]).unwrap();
+        assert_eq!(plan.order, [2, 1, 0]);
+        assert_eq!(plan.dependencies_of(0), [2, 1]);
+        assert_eq!(plan.dependencies_of(1), [2]);
+        assert!(plan.dependencies_of(2).is_empty());
+
+        // Unselected scripts in between are not removed, but still order
+        // the ones that are.
+        let plan = resolve_removal_plan(&scripts, &[0, 2]).unwrap();
+        assert_eq!(plan.order, [2, 0]);
+        assert!(plan.auto_included.is_empty());
+        assert_eq!(plan.dependencies_of(0), [2]);
+    }
+
+    #[test]
+    fn finds_dependents_despite_unresolvable_requirements() {
+        let scripts = [
+            script("debian", "apt.sh", &[]),
+            script("debian", "git.sh", &["apt", "missing"]),
+            script("debian", "flutter.sh", &["git"]),
+            script("debian", "a.sh", &["b"]),
+            script("debian", "b.sh", &["a", "git"]),
+        ];
+        assert_eq!(find_dependents(&scripts, 0), [1, 2, 3, 4]);
+        assert_eq!(find_dependents(&scripts, 1), [2, 3, 4]);
+        assert!(find_dependents(&scripts, 2).is_empty());
+    }
+
+    #[test]
+    fn reports_dependency_cycles() {
+        let scripts = [
+            script("debian", "a.sh", &["b"]),
+            script("debian", "b.sh", &["c"]),
+            script("debian", "c.sh", &["a"]),
+        ];
+        let error = resolve_execution_plan(&scripts, &[0]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Dependency cycle detected: a.sh [debian] -> b.sh [debian] -> c.sh [debian] -> a.sh [debian]"
+        );
+    }
+
+    #[test]
+    fn reports_missing_prerequisites() {
+        let scripts = [script("debian", "a.sh", &["missing"])];
+        let error = resolve_execution_plan(&scripts, &[0]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Script a.sh [debian] requires 'missing', which is not available for this OS"
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use std::process::{Child, Command, ExitStatus};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Time a script gets to exit after SIGTERM before it is killed.
+pub const TERMINATION_GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+/// How often running scripts are checked for exit, timeout and cancellation.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Whether the run was interrupted, e.g. by Ctrl-C.
+pub fn is_interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Marks the run as interrupted so no further scripts are started.
+pub fn request_interrupt() {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Installs handlers so that SIGINT, SIGTERM and SIGHUP cancel the run instead
+/// of killing the runner, letting it stop scripts and clean up after itself.
+#[cfg(unix)]
+pub fn install_interrupt_handler() {
+    extern "C" fn handle_interrupt(_signal: libc::c_int) {
+        INTERRUPTED.store(true, Ordering::SeqCst);
+    }
+
+    // SAFETY: the handler only stores to an atomic, which is async-signal-safe.
+    unsafe {
+        let handler = handle_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        for signal in [libc::SIGINT, libc::SIGTERM, libc::SIGHUP] {
+            libc::signal(signal, handler);
+        }
+        // Needed to take the terminal back from a script's process group.
+        libc::signal(libc::SIGTTOU, libc::SIG_IGN);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn install_interrupt_handler() {}
+
+/// Puts the script in its own process group so it can be stopped together
+/// with everything it started.
+///
+/// With `foreground`, the group also becomes the terminal's foreground group
+/// so the script can prompt for input and receives Ctrl-C directly.
+#[cfg(unix)]
+pub fn isolate(command: &mut Command, foreground: bool) {
+    use std::os::unix::process::CommandExt;
+
+    if !foreground {
+        command.process_group(0);
+        return;
+    }
+
+    // SAFETY: only async-signal-safe libc calls are made between fork and exec.
+    unsafe {
+        command.pre_exec(|| {
+            libc::setpgid(0, 0);
+            libc::signal(libc::SIGTTOU, libc::SIG_IGN);
+            libc::tcsetpgrp(libc::STDIN_FILENO, libc::getpid());
+            libc::signal(libc::SIGTTOU, libc::SIG_DFL);
+            Ok(())
+        });
+    }
+}
+
+#[cfg(not(unix))]
+pub fn isolate(_command: &mut Command, _foreground: bool) {}
+
+/// Gives the terminal back to the runner after a foreground script exits.
+#[cfg(unix)]
+pub fn reclaim_terminal() {
+    // SAFETY: plain libc calls on our own process group and stdin.
+    unsafe {
+        if libc::isatty(libc::STDIN_FILENO) == 1 {
+            libc::tcsetpgrp(libc::STDIN_FILENO, libc::getpgrp());
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub fn reclaim_terminal() {}
+
+/// Stops a script and its process group: SIGTERM first, then SIGKILL once
+/// `grace` has passed.
+pub fn terminate(child: &mut Child, grace: Duration) -> std::io::Result<ExitStatus> {
+    #[cfg(unix)]
+    {
+        use log::{debug, warn};
+        use std::thread;
+        use std::time::Instant;
+
+        let group = -(child.id() as libc::pid_t);
+        debug!("Sending SIGTERM to process group {}", -group);
+        // SAFETY: signalling a process group we created.
+        unsafe {
+            libc::kill(group, libc::SIGTERM);
+        }
+
+        let deadline = Instant::now() + grace;
+        while Instant::now() < deadline {
+            if has_exited(child)? {
+                // Make sure nothing the script started outlives it.
+                // SAFETY: the exited leader is not reaped yet, so its PID and
+                // with it the group ID cannot have been reused.
+                unsafe {
+                    libc::kill(group, libc::SIGKILL);
+                }
+                return child.wait();
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+
+        warn!("Process group {} did not exit, sending SIGKILL", -group);
+        // SAFETY: signalling a process group we created, whose leader has not
+        // been reaped yet.
+        unsafe {
+            libc::kill(group, libc::SIGKILL);
+        }
+        child.wait()
+    }
+
+    #[cfg(not(unix))]
+    {
+        // Windows has no graceful equivalent of SIGTERM for console programs.
+        let _ = grace;
+        child.kill()?;
+        child.wait()
+    }
+}
+
+/// Whether `child` has exited, without reaping it, so its process group can
+/// still be signalled safely. A child that was already reaped counts as exited.
+#[cfg(unix)]
+fn has_exited(child: &Child) -> std::io::Result<bool> {
+    // SAFETY: an all-zero `siginfo_t` is valid, and `WNOWAIT` leaves the
+    // child waitable for `Child::wait`.
+    let (result, info) = unsafe {
+        let mut info: libc::siginfo_t = std::mem::zeroed();
+        let result = libc::waitid(
+            libc::P_PID,
+            child.id() as libc::id_t,
+            &mut info,
+            libc::WEXITED | libc::WNOHANG | libc::WNOWAIT,
+        );
+        (result, info)
+    };
+    if result != 0 {
+        let error = std::io::Error::last_os_error();
+        return match error.raw_os_error() {
+            Some(libc::ECHILD) => Ok(true),
+            _ => Err(error),
+        };
+    }
+    // With WNOHANG, a child that is still running leaves `si_pid` zero.
+    // SAFETY: waitid filled in the fields of a child state change, if any.
+    Ok(unsafe { info.si_pid() } != 0)
+}
+
+/// Whether the script exited because the user pressed Ctrl-C.
+pub fn interrupted_by_user(status: &ExitStatus) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if status.signal() == Some(libc::SIGINT) {
+            return true;
+        }
+    }
+    status.code() == Some(130)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Instant;
+
+    #[test]
+    fn notices_exits_without_reaping_the_child() {
+        let mut child = Command::new("sh").args(["-c", "exit 3"]).spawn().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !has_exited(&child).unwrap() {
+            assert!(Instant::now() < deadline, "child did not exit");
+            thread::sleep(Duration::from_millis(10));
+        }
+        // Still waitable, so the exit status was not lost.
+        assert_eq!(child.wait().unwrap().code(), Some(3));
+    }
+
+    #[test]
+    fn terminates_the_process_group() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "sleep 30 & sleep 30"]);
+        isolate(&mut command, false);
+        let mut child = command.spawn().unwrap();
+        assert!(!has_exited(&child).unwrap());
+
+        let started = Instant::now();
+        let status = terminate(&mut child, Duration::from_secs(5)).unwrap();
+        assert!(!status.success());
+        assert!(started.elapsed() < Duration::from_secs(5));
+        // SAFETY: probing with signal 0 only checks whether the group exists.
+        let group_exists = unsafe { libc::kill(-(child.id() as libc::pid_t), 0) } == 0;
+        assert!(!group_exists);
+    }
+}
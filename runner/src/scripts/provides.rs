@@ -0,0 +1,268 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::scripts::models::ScriptInfo;
+
+/// A command a script makes available, declared with `@provides`, e.g.
+/// `nvim>=0.10` or `podman`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provide {
+    pub command: String,
+    pub constraint: Option<Constraint>,
+}
+
+impl Provide {
+    /// The commands `script` declares in `@provides`.
+    pub fn of(script: &ScriptInfo) -> Vec<Provide> {
+        script
+            .metadata
+            .provides
+            .iter()
+            .filter_map(|provide| Provide::parse(provide).ok())
+            .collect()
+    }
+
+    /// Parses `command` optionally followed by a comparison operator and a
+    /// version, e.g. `nvim >= 0.10`.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let value = value.trim();
+        let Some(start) = value.find(['<', '>', '=']) else {
+            return Provide::new(value, None);
+        };
+        let (command, rest) = value.split_at(start);
+        let (op, version) = if let Some(version) = rest.strip_prefix(">=") {
+            (Op::Ge, version)
+        } else if let Some(version) = rest.strip_prefix("<=") {
+            (Op::Le, version)
+        } else if let Some(version) = rest.strip_prefix("==") {
+            (Op::Eq, version)
+        } else if let Some(version) = rest.strip_prefix('>') {
+            (Op::Gt, version)
+        } else if let Some(version) = rest.strip_prefix('<') {
+            (Op::Lt, version)
+        } else {
+            (Op::Eq, &rest[1..])
+        };
+        let version = Version::parse(version.trim()).ok_or_else(|| {
+            format!(
+                "invalid version `{}` for `{}`, expected e.g. 0.10 or 4.9.3",
+                version.trim(),
+                command.trim()
+            )
+        })?;
+        Provide::new(command, Some(Constraint { op, version }))
+    }
+
+    fn new(command: &str, constraint: Option<Constraint>) -> Result<Self, String> {
+        let command = command.trim();
+        if command.is_empty() || command.contains(char::is_whitespace) {
+            return Err(format!("invalid command name `{}`", command));
+        }
+        Ok(Provide {
+            command: command.to_string(),
+            constraint,
+        })
+    }
+}
+
+impl fmt::Display for Provide {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.command)?;
+        if let Some(constraint) = &self.constraint {
+            write!(f, "{}", constraint)?;
+        }
+        Ok(())
+    }
+}
+
+/// A version requirement such as `>=0.10`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraint {
+    pub op: Op,
+    pub version: Version,
+}
+
+impl Constraint {
+    /// Whether `version` satisfies the requirement. `=0.10` accepts any
+    /// 0.10.x release.
+    pub fn matches(&self, version: &Version) -> bool {
+        match self.op {
+            Op::Eq => version.0.starts_with(&self.version.0),
+            Op::Ge => version.cmp(&self.version) != Ordering::Less,
+            Op::Gt => version.cmp(&self.version) == Ordering::Greater,
+            Op::Le => version.cmp(&self.version) != Ordering::Greater,
+            Op::Lt => version.cmp(&self.version) == Ordering::Less,
+        }
+    }
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.op, self.version)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ge,
+    Gt,
+    Le,
+    Lt,
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Op::Eq => "=",
+            Op::Ge => ">=",
+            Op::Gt => ">",
+            Op::Le => "<=",
+            Op::Lt => "<",
+        })
+    }
+}
+
+/// A dotted numeric version such as `0.10.1`. Missing components compare as
+/// zero, so `0.10` equals `0.10.0`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Version(pub Vec<u64>);
+
+impl Version {
+    /// Parses `1`, `0.10` or `v4.9.3`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.strip_prefix(['v', 'V']).unwrap_or(value);
+        let components: Option<Vec<u64>> = value.split('.').map(|part| part.parse().ok()).collect();
+        components
+            .filter(|components| !components.is_empty())
+            .map(Version)
+    }
+
+    /// The first dotted version in `text`, such as the output of
+    /// `nvim --version` (`NVIM v0.10.1`).
+    pub fn find_in(text: &str) -> Option<Self> {
+        text.split(|c: char| !(c.is_ascii_alphanumeric() || c == '.'))
+            .map(|word| word.trim_end_matches('.'))
+            .filter(|word| word.contains('.'))
+            .find_map(|word| {
+                // Keep only the leading numeric part of e.g. `1.2.3-beta`
+                // or `9.4p1`.
+                let word = word.strip_prefix(['v', 'V']).unwrap_or(word);
+                let end = word
+                    .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                    .unwrap_or(word.len());
+                let numeric = word[..end].trim_end_matches('.');
+                numeric
+                    .contains('.')
+                    .then(|| Version::parse(numeric))
+                    .flatten()
+            })
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let length = self.0.len().max(other.0.len());
+        (0..length)
+            .map(|i| {
+                let a = self.0.get(i).copied().unwrap_or(0);
+                let b = other.0.get(i).copied().unwrap_or(0);
+                a.cmp(&b)
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let components: Vec<String> = self.0.iter().map(ToString::to_string).collect();
+        f.write_str(&components.join("."))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(value: &str) -> Version {
+        Version::parse(value).unwrap()
+    }
+
+    fn constraint(value: &str) -> Constraint {
+        Provide::parse(&format!("tool{}", value))
+            .unwrap()
+            .constraint
+            .unwrap()
+    }
+
+    #[test]
+    fn parses_provides() {
+        let provide = Provide::parse(" nvim >= v0.10 ").unwrap();
+        assert_eq!(provide.command, "nvim");
+        assert_eq!(
+            provide.constraint,
+            Some(Constraint {
+                op: Op::Ge,
+                version: version("0.10")
+            })
+        );
+        assert_eq!(provide.to_string(), "nvim>=0.10");
+        assert_eq!(Provide::parse("podman").unwrap().constraint, None);
+        assert_eq!(constraint("==1.2").op, Op::Eq);
+        assert!(Provide::parse("nvim >= latest").is_err());
+        assert!(Provide::parse(">= 1").is_err());
+        assert!(Provide::parse("two words").is_err());
+    }
+
+    #[test]
+    fn compares_versions_with_missing_components_as_zero() {
+        assert_eq!(version("0.10"), version("v0.10"));
+        assert_eq!(version("0.10").cmp(&version("0.10.0")), Ordering::Equal);
+        assert!(version("0.9.5") < version("0.10"));
+        assert!(version("1") > version("0.99.99"));
+        assert_eq!(Version::parse("1.x"), None);
+        assert_eq!(Version::parse(""), None);
+    }
+
+    #[test]
+    fn matches_constraints() {
+        assert!(constraint(">=0.10").matches(&version("0.10.0")));
+        assert!(constraint(">=0.10").matches(&version("1.0")));
+        assert!(!constraint(">=0.10").matches(&version("0.9.5")));
+        assert!(constraint(">0.10").matches(&version("0.10.1")));
+        assert!(!constraint(">0.10").matches(&version("0.10.0")));
+        assert!(constraint("<=2").matches(&version("2.0.0")));
+        assert!(!constraint("<2").matches(&version("2.0")));
+        // `=` accepts any release of the given version.
+        assert!(constraint("=0.10").matches(&version("0.10.4")));
+        assert!(!constraint("=0.10").matches(&version("0.1")));
+        assert!(!constraint("=0.10").matches(&version("0.100")));
+    }
+
+    #[test]
+    fn finds_versions_in_command_output() {
+        let find = |text| Version::find_in(text).map(|version| version.to_string());
+        assert_eq!(
+            find("NVIM v0.10.1\nBuild type: Release\nLuaJIT 2.1.1713484068"),
+            Some(String::from("0.10.1"))
+        );
+        assert_eq!(
+            find("OpenSSH_9.6p1 Ubuntu-3ubuntu13.5, OpenSSL 3.0.13 30 Jan 2024"),
+            Some(String::from("9.6"))
+        );
+        assert_eq!(
+            find("podman version 4.9.3-dev."),
+            Some(String::from("4.9.3"))
+        );
+        assert_eq!(find("git version 2.45.2."), Some(String::from("2.45.2")));
+        assert_eq!(find("tool 3 (build 1)"), None);
+        assert_eq!(find(""), None);
+    }
+}
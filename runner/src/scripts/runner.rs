@@ -1,38 +1,834 @@
-use anyhow::{Context, Result};
-use log::{info, warn};
-use std::path::{Path, PathBuf};
+use anyhow::{anyhow, Context, Result};
+use jiff::Timestamp;
+use log::{debug, error, info, warn};
+use std::collections::{BTreeMap, HashSet};
+use std::io::{ErrorKind, IsTerminal, Read, Write};
+use std::path::Path;
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
 
+use crate::scripts::action::Action;
+use crate::scripts::check::{run_checks, Check, CheckResult};
+use crate::scripts::context::RuntimeContext;
+use crate::scripts::exports::{ExportFiles, Exports};
+use crate::scripts::interpreter::require_interpreter;
+use crate::scripts::logs::{RunLogs, Transcript};
+use crate::scripts::models::ScriptInfo;
+use crate::scripts::outcome::{RunReport, ScriptOutcome, ScriptStatus};
+use crate::scripts::plan::ExecutionPlan;
+use crate::scripts::process;
+use crate::scripts::shellenv::exports_of;
+
+/// Time the output of a script that exited gets to reach its end before the
+/// run moves on without it.
+const OUTPUT_DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Settings controlling how an execution plan is run.
+#[derive(Debug, Clone)]
+pub struct ExecutionOptions {
+    /// Maximum number of scripts running at the same time.
+    pub jobs: usize,
+    /// Keep running scripts that do not depend on a failed one.
+    pub keep_going: bool,
+    /// Where script output is captured, if anywhere.
+    pub logs: Option<RunLogs>,
+    /// Default time limit for each script, overridden by `@timeout`.
+    pub timeout: Option<Duration>,
+    /// Extra environment variables for every script, e.g. from a profile.
+    pub environment: BTreeMap<String, String>,
+    /// Interpreter for scripts that neither declare one nor have a shebang.
+    pub default_interpreter: String,
+    /// Environment changes exported by scripts that ran before, e.g. in the
+    /// run being resumed.
+    pub exports: Exports,
+    /// Run scripts even if their checks report them as already satisfied.
+    pub force: bool,
+    /// What the scripts are run for; actions other than installing run the
+    /// script's companion for the action, if it has one.
+    pub action: Action,
+}
+
+/// Runs the scripts of `plan` inside `temp_dir` and reports what happened to each.
+///
+/// With more than one job, scripts whose dependencies have completed run
+/// concurrently and their output is prefixed with the script name. Scripts
+/// sharing a `@lock` are serialized and `@interactive` scripts always run alone
+/// with the terminal attached.
+///
+/// Scripts with a `@check` command or a companion check script are only
+/// installed if a check fails, unless `force` is set.
+///
+/// Variables and `PATH` entries exported by a successful script through
+/// `$RUNNER_ENV` and `$RUNNER_PATH` apply to the scripts started after it.
+///
+/// After a failure no new scripts are started, unless `keep_going` is set, in
+/// which case only the scripts depending on the failed one are skipped.
+///
+/// `on_finished` is called as soon as each started script finishes, so callers
+/// can persist progress while the run is still going.
 pub fn run_scripts(
-    scripts: &[PathBuf],
-    selections: &[usize],
+    scripts: &[ScriptInfo],
+    plan: &ExecutionPlan,
     temp_dir: &Path,
-) -> Result<(), anyhow::Error> {
-    for &index in selections {
-        let script = &scripts[index];
-        info!("Running script: {}", script.display());
+    options: &ExecutionOptions,
+    on_finished: &mut dyn FnMut(&ScriptOutcome),
+) -> RunReport {
+    let jobs = options.jobs.max(1);
+    if jobs > 1 {
+        info!("Running up to {} scripts in parallel", jobs);
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    let mut pending = plan.order.clone();
+    let mut running: Vec<usize> = Vec::new();
+    let mut completed: HashSet<usize> = HashSet::new();
+    let mut unsuccessful: HashSet<usize> = HashSet::new();
+    let mut outcomes: Vec<ScriptOutcome> = Vec::new();
+    let mut exports = options.exports.clone();
 
-        let status = std::process::Command::new("bash")
-            .arg(script)
-            .current_dir(temp_dir)
-            .spawn()
-            .with_context(|| format!("Failed to execute script: {}", script.display()))?
-            .wait()
-            .with_context(|| format!("Failed to wait for script: {}", script.display()))?;
+    thread::scope(|scope| loop {
+        // Without --keep-going, stop scheduling new work once a script has
+        // failed, but let the ones already running finish. Running scripts
+        // stop by themselves when the run is interrupted.
+        while !process::is_interrupted()
+            && (options.keep_going || unsuccessful.is_empty())
+            && running.len() < jobs
+        {
+            let Some(position) = pending
+                .iter()
+                .position(|&index| is_ready(scripts, plan, index, &running, &completed))
+            else {
+                break;
+            };
+            let index = pending.remove(position);
+            running.push(index);
+
+            let sender = sender.clone();
+            let script = &scripts[index];
+            let prefix_output = jobs > 1 && !script.metadata.interactive;
+            let exports = exports.clone();
+            scope.spawn(move || {
+                let outcome = run_script(index, script, temp_dir, prefix_output, options, &exports);
+                sender.send(outcome).ok();
+            });
+        }
+
+        if running.is_empty() {
+            break;
+        }
+
+        let Ok(outcome) = receiver.recv() else {
+            break;
+        };
+        running.retain(|&i| i != outcome.index);
+        if let Some(exported) = &outcome.exports {
+            exports.merge(exported);
+        }
+        on_finished(&outcome);
+        // Scripts skipped through `@skip-in` or already satisfied do not hold
+        // back their dependents.
+        if outcome.status.is_complete() {
+            completed.insert(outcome.index);
+        } else {
+            unsuccessful.insert(outcome.index);
+            // Scripts left waiting on a cancelled one are cancelled with the
+            // rest of the run below.
+            if options.keep_going && outcome.status != ScriptStatus::Cancelled {
+                skip_dependents(
+                    scripts,
+                    plan,
+                    &mut pending,
+                    &mut unsuccessful,
+                    &mut outcomes,
+                );
+            }
+        }
+        outcomes.push(outcome);
+    });
+
+    if !pending.is_empty() {
+        warn!("{} scripts were not started", pending.len());
+    }
+    let cancelled = process::is_interrupted();
+    for index in pending {
+        outcomes.push(if cancelled {
+            ScriptOutcome::cancelled(index, "run was cancelled")
+        } else {
+            ScriptOutcome::skipped(index, "not started after an earlier failure")
+        });
+    }
+
+    outcomes.sort_by_key(|outcome| plan.order.iter().position(|&i| i == outcome.index));
+    RunReport { outcomes }
+}
+
+/// Removes pending scripts that (transitively) depend on an unsuccessful one
+/// and records them as skipped.
+fn skip_dependents(
+    scripts: &[ScriptInfo],
+    plan: &ExecutionPlan,
+    pending: &mut Vec<usize>,
+    unsuccessful: &mut HashSet<usize>,
+    outcomes: &mut Vec<ScriptOutcome>,
+) {
+    // Pending scripts are in topological order, so a single pass also catches
+    // scripts depending on ones skipped earlier in the same pass.
+    pending.retain(|&index| {
+        let Some(&blocker) = plan
+            .dependencies_of(index)
+            .iter()
+            .find(|dependency| unsuccessful.contains(dependency))
+        else {
+            return true;
+        };
+        let note = format!("requires {}", scripts[blocker].display_name());
+        warn!("Skipping {}: {}", scripts[index].display_name(), note);
+        unsuccessful.insert(index);
+        outcomes.push(ScriptOutcome::skipped(index, note));
+        false
+    });
+}
+
+/// Environment variables the runner sets for `script`, on top of its own.
+pub fn script_environment(
+    script: &ScriptInfo,
+    temp_dir: &Path,
+    action: Action,
+) -> Vec<(String, String)> {
+    let mut environment = vec![
+        (String::from("RUNNER_SCRIPT"), script.name.clone()),
+        (String::from("RUNNER_ACTION"), action.name().to_string()),
+        (String::from("RUNNER_OS_DIR"), script.os_type.clone()),
+        (
+            String::from("RUNNER_TEMP_DIR"),
+            temp_dir.to_string_lossy().to_string(),
+        ),
+    ];
+    let export_files = ExportFiles::new(script, temp_dir);
+    environment.push((
+        String::from("RUNNER_ENV"),
+        export_files.env.to_string_lossy().to_string(),
+    ));
+    environment.push((
+        String::from("RUNNER_PATH"),
+        export_files.path.to_string_lossy().to_string(),
+    ));
+    environment.push((
+        String::from("RUNNER_SHELL"),
+        export_files.shell.to_string_lossy().to_string(),
+    ));
+    environment.extend(RuntimeContext::current().environment());
+    environment
+}
+
+/// Whether the script at `index` can start given the scripts currently running.
+fn is_ready(
+    scripts: &[ScriptInfo],
+    plan: &ExecutionPlan,
+    index: usize,
+    running: &[usize],
+    completed: &HashSet<usize>,
+) -> bool {
+    let script = &scripts[index];
+    if !plan
+        .dependencies_of(index)
+        .iter()
+        .all(|dependency| completed.contains(dependency))
+    {
+        return false;
+    }
+
+    // Interactive scripts need the terminal to themselves.
+    if script.metadata.interactive && !running.is_empty() {
+        return false;
+    }
+
+    running.iter().all(|&other| {
+        let other = &scripts[other];
+        !other.metadata.interactive
+            && !other
+                .metadata
+                .locks
+                .iter()
+                .any(|lock| script.metadata.locks.contains(lock))
+    })
+}
+
+fn run_script(
+    index: usize,
+    script_info: &ScriptInfo,
+    temp_dir: &Path,
+    prefix_output: bool,
+    options: &ExecutionOptions,
+    exports: &Exports,
+) -> ScriptOutcome {
+    if let Some(reason) = RuntimeContext::current().skip_reason(script_info) {
+        info!("Skipping {}: {}", script_info.display_name(), reason);
+        return ScriptOutcome::skipped(index, reason);
+    }
 
-        if !status.success() {
+    let check_note = if !options.action.is_install() {
+        None
+    } else if options.force {
+        (!Check::of(script_info).is_empty()).then(|| String::from("check skipped with --force"))
+    } else {
+        let started_at = Timestamp::now();
+        let started = Instant::now();
+        let result = run_checks(script_info, &options.default_interpreter, |command| {
+            prepare_command(
+                command,
+                script_info,
+                Action::Install,
+                temp_dir,
+                options,
+                exports,
+            )
+        });
+        match result {
+            Ok(None) => None,
+            Ok(Some(CheckResult::Satisfied(note))) => {
+                info!(
+                    "Skipping {}, already satisfied: {}",
+                    script_info.display_name(),
+                    note
+                );
+                return ScriptOutcome::satisfied(
+                    index,
+                    started_at,
+                    started.elapsed(),
+                    format!("already satisfied: {}", note),
+                );
+            }
+            Ok(Some(CheckResult::NotSatisfied(reason))) => {
+                info!("Running {}: {}", script_info.display_name(), reason);
+                Some(format!("not satisfied: {}", reason))
+            }
+            Ok(Some(CheckResult::Cancelled(reason))) => {
+                warn!("Not running {}: {}", script_info.display_name(), reason);
+                return ScriptOutcome::cancelled(index, reason);
+            }
+            Err(e) => {
+                warn!(
+                    "Could not check {}, running it anyway: {:#}",
+                    script_info.display_name(),
+                    e
+                );
+                Some(format!("check failed to run: {:#}", e))
+            }
+        }
+    };
+
+    let transcript = options
+        .logs
+        .as_ref()
+        .and_then(|logs| match logs.transcript(script_info) {
+            Ok(transcript) => Some(transcript),
+            Err(e) => {
+                warn!(
+                    "Output of {} will not be logged: {:#}",
+                    script_info.display_name(),
+                    e
+                );
+                None
+            }
+        });
+    let output = OutputSink {
+        prefix: prefix_output.then(|| format!("[{}]", script_info.name)),
+        transcript,
+    };
+
+    let timeout = script_info
+        .metadata
+        .timeout
+        .map(Duration::from_secs)
+        .or(options.timeout);
+
+    let export_files = ExportFiles::new(script_info, temp_dir);
+    if let Err(e) = export_files.create() {
+        warn!(
+            "{} will not be able to export variables: {:#}",
+            script_info.display_name(),
+            e
+        );
+    }
+
+    let resolved = options.action.resolve(script_info);
+    let action = resolved
+        .as_ref()
+        .map_or(options.action, |(action, _)| *action);
+    let note = check_note.or_else(|| {
+        (action != options.action)
+            .then(|| format!("no {} action, ran {} instead", options.action, action))
+    });
+    let started_at = Timestamp::now();
+    let started = Instant::now();
+    let result = match &resolved {
+        Some(resolved) => spawn_and_wait(
+            script_info,
+            resolved,
+            temp_dir,
+            output,
+            timeout,
+            options,
+            exports,
+        ),
+        None => Err(anyhow!(
+            "{} has no {} action; add a companion script or declare it in `@actions`",
+            script_info.display_name(),
+            options.action
+        )),
+    };
+    let duration = started.elapsed();
+    let script = &script_info.path;
+
+    let outcome =
+        |status: ScriptStatus, exit_code: Option<i32>, note: Option<String>| ScriptOutcome {
+            index,
+            status,
+            exit_code,
+            started_at: Some(started_at),
+            duration,
+            note,
+            exports: None,
+            shell: None,
+        };
+
+    let mut outcome = match result {
+        Ok(ScriptExit::TimedOut(limit)) => {
+            warn!(
+                "Script timed out after {}s: {}",
+                limit.as_secs(),
+                script.display()
+            );
+            outcome(
+                ScriptStatus::Failed,
+                None,
+                Some(format!("timed out after {}s", limit.as_secs())),
+            )
+        }
+        Ok(ScriptExit::Cancelled) => {
+            warn!("Script cancelled: {}", script.display());
+            outcome(
+                ScriptStatus::Cancelled,
+                None,
+                Some(String::from("cancelled by user")),
+            )
+        }
+        Ok(ScriptExit::Exited(status)) if process::interrupted_by_user(&status) => {
+            // The script had the terminal and received Ctrl-C itself; treat
+            // it as a request to stop the whole run.
+            process::request_interrupt();
+            warn!("Script interrupted: {}", script.display());
+            outcome(
+                ScriptStatus::Cancelled,
+                status.code(),
+                Some(String::from("interrupted by user")),
+            )
+        }
+        Ok(ScriptExit::Exited(status)) if status.success() => {
+            info!("Script completed successfully: {}", script.display());
+            let mut outcome = outcome(ScriptStatus::Ok, status.code(), None);
+            match action {
+                // Shell settings of a removed script would point at nothing.
+                Action::Remove => outcome.shell = Some(Vec::new()),
+                Action::Install => read_exports(script_info, temp_dir, &export_files, &mut outcome),
+                Action::Upgrade => {
+                    read_exports(script_info, temp_dir, &export_files, &mut outcome);
+                    // An upgrade that declares nothing keeps the settings of
+                    // the install.
+                    if outcome.shell.as_ref().is_some_and(Vec::is_empty) {
+                        outcome.shell = None;
+                    }
+                }
+            }
+            outcome
+        }
+        Ok(ScriptExit::Exited(status)) => {
             let exit_code = status.code().unwrap_or(-1);
             warn!(
                 "Script failed: {} with exit code: {}",
                 script.display(),
                 exit_code
             );
-            return Err(anyhow::anyhow!(
-                "Script failed: {} with exit code: {:?}",
-                script.display(),
-                status.code()
-            ));
+            outcome(
+                ScriptStatus::Failed,
+                status.code(),
+                status
+                    .code()
+                    .is_none()
+                    .then(|| String::from("terminated by signal")),
+            )
+        }
+        Err(e) => {
+            error!("{:#}", e);
+            outcome(ScriptStatus::Failed, None, Some(format!("{:#}", e)))
+        }
+    };
+    if outcome.note.is_none() {
+        outcome.note = note;
+    }
+    outcome
+}
+
+/// Stores what a successful script exported for later scripts and the
+/// user's shells in its outcome.
+fn read_exports(
+    script_info: &ScriptInfo,
+    temp_dir: &Path,
+    export_files: &ExportFiles,
+    outcome: &mut ScriptOutcome,
+) {
+    let mut exported = match Exports::read(script_info, temp_dir) {
+        Ok(exported) => exported,
+        Err(e) => {
+            warn!(
+                "Ignoring exports of {}: {:#}",
+                script_info.display_name(),
+                e
+            );
+            Exports::default()
+        }
+    };
+    match export_files.shell_declarations() {
+        Ok(declarations) => {
+            // Variables and directories persisted for the user's
+            // shells apply to the rest of the run too.
+            if let Some(declarations) = &declarations {
+                exported.merge(&exports_of(declarations));
+            }
+            outcome.shell = declarations;
+        }
+        Err(e) => warn!(
+            "Ignoring shell settings of {}: {:#}",
+            script_info.display_name(),
+            e
+        ),
+    }
+    if !exported.is_empty() {
+        info!(
+            "{} exported {} variables and {} PATH entries for later scripts",
+            script_info.display_name(),
+            exported.variables.len(),
+            exported.path.len()
+        );
+        outcome.exports = Some(exported);
+    }
+}
+
+/// Destination of a script's output: the terminal, optionally prefixed with
+/// the script name, and the script's transcript when logging is enabled.
+struct OutputSink {
+    prefix: Option<String>,
+    transcript: Option<Transcript>,
+}
+
+impl OutputSink {
+    /// Whether the output needs to pass through the runner instead of going
+    /// straight to the terminal.
+    fn is_captured(&self) -> bool {
+        self.prefix.is_some() || self.transcript.is_some()
+    }
+
+    /// Passes output through to the terminal as soon as it arrives, so
+    /// prompts without a trailing newline show up. Prefixed output is only
+    /// written in whole lines by [`OutputSink::write_line`].
+    fn write_chunk(&self, bytes: &[u8], is_stderr: bool) {
+        if self.prefix.is_some() {
+            return;
+        }
+        if is_stderr {
+            let mut stderr = std::io::stderr().lock();
+            stderr.write_all(bytes).and_then(|_| stderr.flush()).ok();
+        } else {
+            let mut stdout = std::io::stdout().lock();
+            stdout.write_all(bytes).and_then(|_| stdout.flush()).ok();
         }
-        info!("Script completed successfully: {}", script.display());
     }
+
+    /// Writes a complete line to the terminal if it is prefixed, and to the
+    /// transcript.
+    fn write_line(&self, line: &str, is_stderr: bool) {
+        match (&self.prefix, is_stderr) {
+            (Some(prefix), true) => eprintln!("{} {}", prefix, line),
+            (Some(prefix), false) => println!("{} {}", prefix, line),
+            (None, _) => {}
+        }
+        if let Some(transcript) = &self.transcript {
+            transcript.write_line(line, is_stderr);
+        }
+    }
+}
+
+/// How a started script ended.
+enum ScriptExit {
+    Exited(ExitStatus),
+    /// Stopped after exceeding its time limit.
+    TimedOut(Duration),
+    /// Stopped because the run was interrupted.
+    Cancelled,
+}
+
+/// Runs `action_script` to perform `action` for `script_info`.
+fn spawn_and_wait(
+    script_info: &ScriptInfo,
+    (action, action_script): &(Action, ScriptInfo),
+    temp_dir: &Path,
+    mut output: OutputSink,
+    timeout: Option<Duration>,
+    options: &ExecutionOptions,
+    exports: &Exports,
+) -> Result<ScriptExit> {
+    let script = &action_script.path;
+    if action.is_install() {
+        info!("Running script: {}", script.display());
+    } else {
+        info!(
+            "Running {} action of {}: {}",
+            action,
+            script_info.display_name(),
+            script.display()
+        );
+    }
+    if script_info.metadata.needs_root {
+        info!(
+            "Script may prompt for elevated privileges: {}",
+            script.display()
+        );
+    }
+    if script_info.metadata.interactive {
+        info!("Script expects interactive input: {}", script.display());
+        // Interactive scripts keep the terminal, so their output is not captured.
+        if let Some(transcript) = output.transcript.take() {
+            transcript.finish("output not captured for interactive script");
+        }
+    }
+
+    let (interpreter, program) = require_interpreter(action_script, &options.default_interpreter)?;
+    debug!(
+        "Using interpreter {} (from {})",
+        program.display(),
+        interpreter.source
+    );
+    let mut command = Command::new(program);
+    command.args(&interpreter.args).arg(script);
+    prepare_command(
+        &mut command,
+        script_info,
+        *action,
+        temp_dir,
+        options,
+        exports,
+    )?;
+    if output.prefix.is_some() {
+        command.stdin(Stdio::null());
+    }
+    if output.is_captured() {
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    }
+    // A script running alone on a terminal gets it as its foreground process
+    // group, so it can prompt and Ctrl-C reaches it directly.
+    let foreground = output.prefix.is_none() && std::io::stdin().is_terminal();
+    process::isolate(&mut command, foreground);
+
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("Failed to execute script: {}", script.display()))?;
+
+    let output = Arc::new(output);
+    let mut forwarders = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        forwarders.push(forward_output(stdout, Arc::clone(&output), false));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        forwarders.push(forward_output(stderr, Arc::clone(&output), true));
+    }
+
+    // A limit too far in the future to represent is no limit at all.
+    let deadline = timeout.and_then(|limit| Some((limit, Instant::now().checked_add(limit)?)));
+    let result = loop {
+        if let Some(status) = child
+            .try_wait()
+            .with_context(|| format!("Failed to wait for script: {}", script.display()))?
+        {
+            break ScriptExit::Exited(status);
+        }
+        if process::is_interrupted() {
+            process::terminate(&mut child, process::TERMINATION_GRACE_PERIOD)?;
+            break ScriptExit::Cancelled;
+        }
+        if let Some((limit, deadline)) = deadline {
+            if Instant::now() >= deadline {
+                process::terminate(&mut child, process::TERMINATION_GRACE_PERIOD)?;
+                break ScriptExit::TimedOut(limit);
+            }
+        }
+        thread::sleep(process::POLL_INTERVAL);
+    };
+    if foreground {
+        process::reclaim_terminal();
+    }
+
+    finish_forwarders(forwarders, script);
+    if let Some(transcript) = &output.transcript {
+        match &result {
+            ScriptExit::Exited(status) => match status.code() {
+                Some(code) => transcript.finish(&format!("exited with code {}", code)),
+                None => transcript.finish("terminated by signal"),
+            },
+            ScriptExit::TimedOut(limit) => {
+                transcript.finish(&format!("timed out after {}s", limit.as_secs()))
+            }
+            ScriptExit::Cancelled => transcript.finish("cancelled"),
+        }
+    }
+    Ok(result)
+}
+
+/// Gives `command` the working directory and environment of `script_info`
+/// performing `action`.
+fn prepare_command(
+    command: &mut Command,
+    script_info: &ScriptInfo,
+    action: Action,
+    temp_dir: &Path,
+    options: &ExecutionOptions,
+    exports: &Exports,
+) -> Result<()> {
+    command
+        .current_dir(temp_dir)
+        .envs(exports.environment()?)
+        .envs(&options.environment)
+        .envs(script_environment(script_info, temp_dir, action));
     Ok(())
 }
+
+/// Copies a child's output stream to `output`: unprefixed output as it
+/// arrives, and line by line to the transcript.
+fn forward_output<R: Read + Send + 'static>(
+    mut stream: R,
+    output: Arc<OutputSink>,
+    is_stderr: bool,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut chunk = [0u8; 8192];
+        let mut pending = Vec::new();
+        loop {
+            let read = match stream.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    debug!("Stopped reading script output: {}", e);
+                    break;
+                }
+            };
+            output.write_chunk(&chunk[..read], is_stderr);
+            pending.extend_from_slice(&chunk[..read]);
+            while let Some(end) = pending.iter().position(|&byte| byte == b'\n') {
+                let line: Vec<u8> = pending.drain(..=end).collect();
+                output.write_line(&decode_line(&line), is_stderr);
+            }
+        }
+        if !pending.is_empty() {
+            output.write_line(&decode_line(&pending), is_stderr);
+        }
+    })
+}
+
+fn decode_line(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes)
+        .trim_end_matches(['\r', '\n'])
+        .to_string()
+}
+
+/// Waits for the output forwarders of an exited script until
+/// [`OUTPUT_DRAIN_TIMEOUT`] has passed. A background process the script left
+/// behind may keep its output open indefinitely; its forwarder is then left
+/// running instead of holding up the run.
+fn finish_forwarders(forwarders: Vec<thread::JoinHandle<()>>, script: &Path) {
+    let deadline = Instant::now() + OUTPUT_DRAIN_TIMEOUT;
+    while forwarders.iter().any(|forwarder| !forwarder.is_finished()) && Instant::now() < deadline {
+        thread::sleep(process::POLL_INTERVAL);
+    }
+    for forwarder in forwarders {
+        if forwarder.is_finished() {
+            forwarder.join().ok();
+        } else {
+            warn!(
+                "Output of {} is still held open by a background process; not waiting for it",
+                script.display()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(name: &str) -> ScriptInfo {
+        ScriptInfo::for_test("unix", name)
+    }
+
+    fn locked(name: &str, lock: &str) -> ScriptInfo {
+        let mut script = script(name);
+        script.metadata.locks = vec![lock.to_string()];
+        script
+    }
+
+    fn interactive(name: &str) -> ScriptInfo {
+        let mut script = script(name);
+        script.metadata.interactive = true;
+        script
+    }
+
+    fn independent(scripts: &[ScriptInfo]) -> ExecutionPlan {
+        ExecutionPlan {
+            order: (0..scripts.len()).collect(),
+            dependencies: (0..scripts.len())
+                .map(|index| (index, Vec::new()))
+                .collect(),
+            ..ExecutionPlan::default()
+        }
+    }
+
+    #[test]
+    fn waits_for_dependencies_to_complete() {
+        let scripts = [script("a.sh"), script("b.sh")];
+        let mut plan = independent(&scripts);
+        plan.dependencies.insert(1, vec![0]);
+
+        let mut completed = HashSet::new();
+        assert!(is_ready(&scripts, &plan, 0, &[], &completed));
+        assert!(!is_ready(&scripts, &plan, 1, &[0], &completed));
+        completed.insert(0);
+        assert!(is_ready(&scripts, &plan, 1, &[], &completed));
+    }
+
+    #[test]
+    fn serializes_scripts_sharing_a_lock() {
+        let scripts = [
+            locked("apt-a.sh", "apt"),
+            locked("apt-b.sh", "apt"),
+            locked("brew.sh", "brew"),
+            script("plain.sh"),
+        ];
+        let plan = independent(&scripts);
+        let completed = HashSet::new();
+        assert!(!is_ready(&scripts, &plan, 1, &[0], &completed));
+        assert!(is_ready(&scripts, &plan, 2, &[0], &completed));
+        assert!(is_ready(&scripts, &plan, 3, &[0, 2], &completed));
+    }
+
+    #[test]
+    fn runs_interactive_scripts_alone() {
+        let scripts = [interactive("prompt.sh"), script("a.sh")];
+        let plan = independent(&scripts);
+        let completed = HashSet::new();
+        assert!(is_ready(&scripts, &plan, 0, &[], &completed));
+        assert!(!is_ready(&scripts, &plan, 0, &[1], &completed));
+        assert!(!is_ready(&scripts, &plan, 1, &[0], &completed));
+    }
+}
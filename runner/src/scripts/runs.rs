@@ -0,0 +1,226 @@
+use anyhow::{anyhow, Context, Result};
+use jiff::Timestamp;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::scripts::action::Action;
+use crate::scripts::exports::Exports;
+use crate::scripts::models::ScriptInfo;
+use crate::scripts::outcome::{ScriptOutcome, ScriptStatus};
+use crate::scripts::plan::ExecutionPlan;
+use crate::scripts::state::state_dir;
+
+/// Progress of one script of a recorded run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunEntry {
+    pub script: String,
+    pub os_dir: String,
+    /// `None` until the script has finished.
+    pub status: Option<ScriptStatus>,
+    pub exit_code: Option<i32>,
+}
+
+impl RunEntry {
+    fn is_for(&self, script: &ScriptInfo) -> bool {
+        self.script == script.name && self.os_dir == script.os_type
+    }
+}
+
+/// A persisted run: what was selected, how it was run and how far it got.
+///
+/// Stored as `<state dir>/runs/<id>.json` and updated after every script so
+/// that an interrupted or failed run can be resumed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub id: String,
+    pub created_at: Timestamp,
+    /// Scripts explicitly selected by the user, as `(name, os_dir)` entries.
+    pub selections: Vec<RunEntry>,
+    /// Every script of the resolved plan, in execution order.
+    pub plan: Vec<RunEntry>,
+    #[serde(flatten)]
+    pub settings: RunSettings,
+    /// Environment changes exported by the scripts that succeeded so far,
+    /// restored when the run is resumed.
+    #[serde(default, skip_serializing_if = "Exports::is_empty")]
+    pub exports: Exports,
+}
+
+/// How a run was executed, reused when it is resumed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunSettings {
+    pub jobs: usize,
+    pub keep_going: bool,
+    /// Default script time limit in seconds, if one was given.
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// Profile the run was started with.
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// Extra environment variables passed to the scripts.
+    #[serde(default)]
+    pub environment: BTreeMap<String, String>,
+    /// Whether scripts run regardless of their checks.
+    #[serde(default)]
+    pub force: bool,
+    /// Number of most recent runs whose logs are kept, if one was given.
+    #[serde(default)]
+    pub keep_logs: Option<usize>,
+    /// What the scripts were run for.
+    #[serde(default, skip_serializing_if = "Action::is_install")]
+    pub action: Action,
+}
+
+impl RunRecord {
+    pub fn new(
+        scripts: &[ScriptInfo],
+        selections: &[usize],
+        plan: &ExecutionPlan,
+        settings: RunSettings,
+    ) -> Self {
+        let created_at = Timestamp::now();
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        let entry = |index: &usize| RunEntry {
+            script: scripts[*index].name.clone(),
+            os_dir: scripts[*index].os_type.clone(),
+            status: None,
+            exit_code: None,
+        };
+
+        RunRecord {
+            id: format!("{}-{}", created_at.strftime("%Y%m%d-%H%M%S"), &suffix[..6]),
+            created_at,
+            selections: selections.iter().map(entry).collect(),
+            plan: plan.order.iter().map(entry).collect(),
+            settings,
+            exports: Exports::default(),
+        }
+    }
+
+    /// Loads the run with the given ID.
+    pub fn load(id: &str) -> Result<Self> {
+        let path = runs_dir()?.join(format!("{}.json", id));
+        if !path.exists() {
+            return Err(anyhow!("No recorded run with ID '{}'", id));
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read run record: {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse run record: {}", path.display()))
+    }
+
+    /// Loads every recorded run, oldest first. Unreadable records are skipped.
+    pub fn load_all() -> Result<Vec<Self>> {
+        let dir = runs_dir()?;
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut runs = Vec::new();
+        for entry in fs::read_dir(&dir)?.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let parsed = fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| Ok(serde_json::from_str::<RunRecord>(&content)?));
+            match parsed {
+                Ok(run) => runs.push(run),
+                Err(e) => warn!("Ignoring unreadable run record {}: {}", path.display(), e),
+            }
+        }
+        runs.sort_by_key(|run| run.created_at);
+        Ok(runs)
+    }
+
+    /// Most recent run that did not complete.
+    pub fn latest_incomplete() -> Result<Option<Self>> {
+        Ok(Self::load_all()?
+            .into_iter()
+            .rev()
+            .find(|run| !run.is_complete()))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let dir = runs_dir()?;
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create runs directory: {}", dir.display()))?;
+        let path = dir.join(format!("{}.json", self.id));
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write run record: {}", path.display()))?;
+        debug!("Saved run record {}", path.display());
+        Ok(())
+    }
+
+    /// Stores the outcome of a finished script.
+    pub fn update(&mut self, script: &ScriptInfo, outcome: &ScriptOutcome) {
+        if let Some(entry) = self.plan.iter_mut().find(|entry| entry.is_for(script)) {
+            entry.status = Some(outcome.status);
+            entry.exit_code = outcome.exit_code;
+        }
+        if let Some(exported) = &outcome.exports {
+            self.exports.merge(exported);
+        }
+    }
+
+    /// Marks a script as deliberately skipped, e.g. with `resume --skip-failed`.
+    pub fn mark_skipped(&mut self, script: &ScriptInfo) {
+        if let Some(entry) = self.plan.iter_mut().find(|entry| entry.is_for(script)) {
+            entry.status = Some(ScriptStatus::Skipped);
+        }
+    }
+
+    /// Whether every script of the plan succeeded, was already satisfied or
+    /// was deliberately skipped.
+    pub fn is_complete(&self) -> bool {
+        self.plan
+            .iter()
+            .all(|entry| entry.status.is_some_and(ScriptStatus::is_complete))
+    }
+
+    /// Indices of the selected scripts among `scripts`.
+    pub fn resolve_selections(&self, scripts: &[ScriptInfo]) -> Result<Vec<usize>> {
+        resolve_entries(&self.selections, scripts)
+    }
+
+    /// Indices of the scripts of this run with the given status.
+    pub fn indices_with_status(
+        &self,
+        scripts: &[ScriptInfo],
+        status: ScriptStatus,
+    ) -> Result<Vec<usize>> {
+        let entries: Vec<RunEntry> = self
+            .plan
+            .iter()
+            .filter(|entry| entry.status == Some(status))
+            .cloned()
+            .collect();
+        resolve_entries(&entries, scripts)
+    }
+}
+
+fn resolve_entries(entries: &[RunEntry], scripts: &[ScriptInfo]) -> Result<Vec<usize>> {
+    entries
+        .iter()
+        .map(|entry| {
+            scripts
+                .iter()
+                .position(|script| entry.is_for(script))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Script {} [{}] from the recorded run is no longer available",
+                        entry.script,
+                        entry.os_dir
+                    )
+                })
+        })
+        .collect()
+}
+
+fn runs_dir() -> Result<PathBuf> {
+    Ok(state_dir()?.join("runs"))
+}
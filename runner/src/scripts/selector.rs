@@ -0,0 +1,320 @@
+use anyhow::{anyhow, Context, Result};
+use glob::Pattern;
+use log::warn;
+use std::path::Path;
+
+use crate::scripts::models::ScriptInfo;
+
+/// A way of referring to one or more scripts on the command line.
+///
+/// Supported forms:
+///
+/// - `nodejs` or `nodejs.sh`: a script by stem or file name
+/// - `debian/nodejs` or `debian/nodejs.sh`: qualified with its OS directory
+/// - `nodejs.sh [debian]`: the name shown by `list`
+/// - `docker-*`: a glob over stems, file names and qualified names
+/// - `tag:dev`: every script tagged `dev`
+#[derive(Debug, Clone)]
+pub enum Selector {
+    Tag(String),
+    Glob(Pattern),
+    Qualified { os_dir: String, name: String },
+    Name(String),
+}
+
+impl Selector {
+    pub fn parse(selector: &str) -> Result<Self> {
+        let selector = selector.trim();
+        if selector.is_empty() {
+            return Err(anyhow!("Empty script selector"));
+        }
+
+        if let Some(tag) = selector.strip_prefix("tag:") {
+            if tag.is_empty() {
+                return Err(anyhow!("Selector '{}' is missing a tag name", selector));
+            }
+            return Ok(Selector::Tag(tag.to_string()));
+        }
+
+        if selector.contains(['*', '?', '[']) && !is_display_name(selector) {
+            let pattern = Pattern::new(selector)
+                .with_context(|| format!("Invalid glob in selector '{}'", selector))?;
+            return Ok(Selector::Glob(pattern));
+        }
+
+        if let Some((name, os_dir)) = split_display_name(selector) {
+            return Ok(Selector::Qualified {
+                os_dir: os_dir.to_string(),
+                name: name.to_string(),
+            });
+        }
+
+        if let Some((os_dir, name)) = selector.split_once('/') {
+            return Ok(Selector::Qualified {
+                os_dir: os_dir.to_string(),
+                name: name.to_string(),
+            });
+        }
+
+        Ok(Selector::Name(selector.to_string()))
+    }
+
+    /// Whether the selector may match several scripts on purpose.
+    fn selects_many(&self) -> bool {
+        matches!(self, Selector::Tag(_) | Selector::Glob(_))
+    }
+
+    fn matches(&self, script: &ScriptInfo) -> bool {
+        match self {
+            Selector::Tag(tag) => script
+                .metadata
+                .tags
+                .iter()
+                .any(|script_tag| script_tag.eq_ignore_ascii_case(tag)),
+            Selector::Glob(pattern) => {
+                pattern.matches(&script.name)
+                    || pattern.matches(stem(script))
+                    || pattern.matches(&qualified_name(script))
+            }
+            Selector::Qualified { os_dir, name } => {
+                script.os_type == *os_dir && matches_name(script, name)
+            }
+            Selector::Name(name) => matches_name(script, name),
+        }
+    }
+
+    /// Whether a qualified selector names a script that `script` replaces.
+    fn matches_override(&self, script: &ScriptInfo) -> bool {
+        let Selector::Qualified { os_dir, name } = self else {
+            return false;
+        };
+        script
+            .overrides
+            .iter()
+            .filter_map(|qualified| qualified.split_once('/'))
+            .any(|(other_dir, other_name)| {
+                other_dir == os_dir && (other_name == name || file_stem(other_name) == name)
+            })
+    }
+}
+
+/// Resolves `selectors` to indices into `scripts`, in selection order and
+/// without duplicates.
+///
+/// Fails if a selector matches nothing, or if a name matches several
+/// scripts, listing the candidates so the user can qualify it. A name also
+/// matches the scripts in other OS directories that a collected script
+/// replaces (see [`ScriptInfo::overrides`]), so `nodejs` is ambiguous when
+/// both `debian/nodejs.sh` and `unix/nodejs.sh` exist.
+pub fn resolve_selectors(scripts: &[ScriptInfo], selectors: &[String]) -> Result<Vec<usize>> {
+    resolve(scripts, selectors, false)
+}
+
+/// Like [`resolve_selectors`], but selectors matching nothing are only
+/// reported as warnings. Used for profiles, which are often shared between
+/// machines that do not have the same scripts available.
+pub fn resolve_available_selectors(
+    scripts: &[ScriptInfo],
+    selectors: &[String],
+) -> Result<Vec<usize>> {
+    resolve(scripts, selectors, true)
+}
+
+fn resolve(
+    scripts: &[ScriptInfo],
+    selectors: &[String],
+    allow_missing: bool,
+) -> Result<Vec<usize>> {
+    let mut selections = Vec::new();
+
+    for raw in selectors {
+        let selector = Selector::parse(raw)?;
+        let matched: Vec<usize> = scripts
+            .iter()
+            .enumerate()
+            .filter(|(_, script)| selector.matches(script))
+            .map(|(index, _)| index)
+            .collect();
+
+        if matched.is_empty() {
+            if let Some(replacement) = scripts
+                .iter()
+                .find(|script| selector.matches_override(script))
+            {
+                return Err(anyhow!(
+                    "'{}' is replaced by {} on this system",
+                    raw,
+                    qualified_name(replacement)
+                ));
+            }
+            if allow_missing {
+                warn!("No script matches '{}' on this system", raw);
+                continue;
+            }
+            return Err(anyhow!("No script matches '{}'", raw));
+        }
+        if !selector.selects_many() {
+            let candidates: Vec<String> = matched
+                .iter()
+                .flat_map(|&index| {
+                    let script = &scripts[index];
+                    let overrides = match selector {
+                        Selector::Name(_) => script.overrides.as_slice(),
+                        _ => &[],
+                    };
+                    std::iter::once(qualified_name(script)).chain(overrides.iter().cloned())
+                })
+                .collect();
+            if candidates.len() > 1 {
+                return Err(anyhow!(
+                    "'{}' is ambiguous, it matches: {}",
+                    raw,
+                    candidates.join(", ")
+                ));
+            }
+        }
+
+        for index in matched {
+            if !selections.contains(&index) {
+                selections.push(index);
+            }
+        }
+    }
+
+    Ok(selections)
+}
+
+/// Name of a script qualified with its OS directory, e.g. `debian/nodejs.sh`.
+pub fn qualified_name(script: &ScriptInfo) -> String {
+    format!("{}/{}", script.os_type, script.name)
+}
+
+fn stem(script: &ScriptInfo) -> &str {
+    file_stem(&script.name)
+}
+
+fn file_stem(name: &str) -> &str {
+    Path::new(name)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(name)
+}
+
+fn matches_name(script: &ScriptInfo, name: &str) -> bool {
+    script.name == name || stem(script) == name
+}
+
+fn is_display_name(selector: &str) -> bool {
+    split_display_name(selector).is_some()
+}
+
+/// Splits `nodejs.sh [debian]` into `("nodejs.sh", "debian")`.
+fn split_display_name(selector: &str) -> Option<(&str, &str)> {
+    let (name, rest) = selector.split_once(" [")?;
+    let os_dir = rest.strip_suffix(']')?;
+    Some((name.trim(), os_dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(os_type: &str, name: &str, tags: &[&str]) -> ScriptInfo {
+        let mut script = ScriptInfo::for_test(os_type, name);
+        script.metadata.tags = tags.iter().map(|tag| tag.to_string()).collect();
+        script
+    }
+
+    fn scripts() -> Vec<ScriptInfo> {
+        vec![
+            script("debian", "nodejs.sh", &["dev"]),
+            script("debian", "docker-ce.sh", &[]),
+            script("unix", "docker-compose.sh", &["Dev"]),
+            script("unix", "fonts.sh", &[]),
+            script("unix", "fonts.py", &[]),
+        ]
+    }
+
+    fn select(selectors: &[&str]) -> Result<Vec<usize>> {
+        let selectors: Vec<String> = selectors.iter().map(|s| s.to_string()).collect();
+        resolve_selectors(&scripts(), &selectors)
+    }
+
+    #[test]
+    fn parses_selector_kinds() {
+        assert!(matches!(Selector::parse("tag:dev"), Ok(Selector::Tag(tag)) if tag == "dev"));
+        assert!(matches!(Selector::parse("docker-*"), Ok(Selector::Glob(_))));
+        assert!(matches!(
+            Selector::parse("debian/nodejs"),
+            Ok(Selector::Qualified { os_dir, name }) if os_dir == "debian" && name == "nodejs"
+        ));
+        assert!(matches!(
+            Selector::parse("nodejs.sh [debian]"),
+            Ok(Selector::Qualified { os_dir, name }) if os_dir == "debian" && name == "nodejs.sh"
+        ));
+        assert!(
+            matches!(Selector::parse(" nodejs "), Ok(Selector::Name(name)) if name == "nodejs")
+        );
+        assert!(Selector::parse("").is_err());
+        assert!(Selector::parse("tag:").is_err());
+    }
+
+    #[test]
+    fn selects_by_name_stem_and_qualified_name() {
+        assert_eq!(select(&["nodejs"]).unwrap(), [0]);
+        assert_eq!(select(&["nodejs.sh"]).unwrap(), [0]);
+        assert_eq!(select(&["debian/nodejs"]).unwrap(), [0]);
+        assert_eq!(select(&["nodejs.sh [debian]"]).unwrap(), [0]);
+        assert!(select(&["unix/nodejs"]).is_err());
+    }
+
+    #[test]
+    fn selects_many_through_tags_and_globs_without_duplicates() {
+        assert_eq!(select(&["tag:DEV"]).unwrap(), [0, 2]);
+        assert_eq!(select(&["docker-*"]).unwrap(), [1, 2]);
+        assert_eq!(select(&["unix/docker*"]).unwrap(), [2]);
+        assert_eq!(select(&["docker-compose", "tag:dev"]).unwrap(), [2, 0]);
+    }
+
+    #[test]
+    fn rejects_names_matching_scripts_in_several_os_directories() {
+        let mut nodejs = script("debian", "nodejs.sh", &[]);
+        nodejs.overrides = vec![String::from("unix/nodejs.sh")];
+        let scripts = [nodejs];
+        let select = |selector: &str| resolve_selectors(&scripts, &[selector.to_string()]);
+
+        assert_eq!(
+            select("nodejs").unwrap_err().to_string(),
+            "'nodejs' is ambiguous, it matches: debian/nodejs.sh, unix/nodejs.sh"
+        );
+        assert_eq!(
+            select("nodejs.sh").unwrap_err().to_string(),
+            "'nodejs.sh' is ambiguous, it matches: debian/nodejs.sh, unix/nodejs.sh"
+        );
+        assert_eq!(select("debian/nodejs").unwrap(), [0]);
+        assert_eq!(select("nodejs.sh [debian]").unwrap(), [0]);
+        assert_eq!(select("node*").unwrap(), [0]);
+        assert_eq!(
+            select("unix/nodejs").unwrap_err().to_string(),
+            "'unix/nodejs' is replaced by debian/nodejs.sh on this system"
+        );
+    }
+
+    #[test]
+    fn rejects_ambiguous_and_unknown_selectors() {
+        assert_eq!(
+            select(&["fonts"]).unwrap_err().to_string(),
+            "'fonts' is ambiguous, it matches: unix/fonts.sh, unix/fonts.py"
+        );
+        assert_eq!(select(&["fonts.py"]).unwrap(), [4]);
+        assert_eq!(
+            select(&["rust"]).unwrap_err().to_string(),
+            "No script matches 'rust'"
+        );
+        let selectors = vec![String::from("rust"), String::from("nodejs")];
+        assert_eq!(
+            resolve_available_selectors(&scripts(), &selectors).unwrap(),
+            [0]
+        );
+    }
+}
@@ -0,0 +1,491 @@
+use anyhow::{anyhow, Context, Result};
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::scripts::exports::Exports;
+use crate::scripts::state::state_dir;
+
+/// Shells whose configuration files the runner manages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    pub const ALL: [Shell; 3] = [Shell::Bash, Shell::Zsh, Shell::Fish];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Shell::ALL
+            .into_iter()
+            .find(|shell| shell.to_string().eq_ignore_ascii_case(name))
+    }
+
+    /// The user's login shell according to `$SHELL`, if it is a known one.
+    pub fn login() -> Option<Self> {
+        let shell = PathBuf::from(env::var_os("SHELL")?);
+        Shell::from_name(&shell.file_name()?.to_string_lossy())
+    }
+
+    /// `~/.bashrc`, `~/.zshrc` or `~/.config/fish/config.fish`.
+    pub fn rc_file(self) -> Option<PathBuf> {
+        let home = PathBuf::from(env::var_os("HOME")?);
+        Some(match self {
+            Shell::Bash => home.join(".bashrc"),
+            Shell::Zsh => env::var_os("ZDOTDIR")
+                .filter(|dir| !dir.is_empty())
+                .map_or(home, PathBuf::from)
+                .join(".zshrc"),
+            Shell::Fish => env::var_os("XDG_CONFIG_HOME")
+                .filter(|dir| !dir.is_empty())
+                .map_or_else(|| home.join(".config"), PathBuf::from)
+                .join("fish")
+                .join("config.fish"),
+        })
+    }
+}
+
+impl fmt::Display for Shell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Fish => "fish",
+        })
+    }
+}
+
+/// A shell setting declared by a script through `$RUNNER_SHELL`, one per
+/// line:
+///
+/// ```text
+/// env PYENV_ROOT=/home/me/.pyenv
+/// path /home/me/.pyenv/bin
+/// alias flutter=fvm flutter
+/// line bash eval "$(pyenv init - bash)"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Declaration {
+    /// An exported environment variable.
+    Env {
+        name: String,
+        value: String,
+    },
+    /// A directory prepended to `PATH` unless it is already there.
+    Path(String),
+    Alias {
+        name: String,
+        command: String,
+    },
+    /// A line of shell code for a single shell.
+    Line {
+        shell: Shell,
+        code: String,
+    },
+}
+
+impl Declaration {
+    /// The declaration as code for `shell`, or `None` if it is meant for
+    /// another shell.
+    fn render(&self, shell: Shell) -> Option<String> {
+        let code = match (self, shell) {
+            (Declaration::Env { name, value }, Shell::Fish) => {
+                format!("set -gx {} {}", name, fish_quote(value))
+            }
+            (Declaration::Env { name, value }, _) => format!("export {}={}", name, sh_quote(value)),
+            (Declaration::Path(dir), Shell::Fish) => format!(
+                "contains -- {0} $PATH; or set -gx PATH {0} $PATH",
+                fish_quote(dir)
+            ),
+            (Declaration::Path(dir), _) => format!(
+                "case \":$PATH:\" in *:{0}:*) ;; *) export PATH={0}:\"$PATH\" ;; esac",
+                sh_quote(dir)
+            ),
+            (Declaration::Alias { name, command }, Shell::Fish) => {
+                format!("alias {} {}", name, fish_quote(command))
+            }
+            (Declaration::Alias { name, command }, _) => {
+                format!("alias {}={}", name, sh_quote(command))
+            }
+            (
+                Declaration::Line {
+                    shell: target,
+                    code,
+                },
+                shell,
+            ) => {
+                if *target != shell {
+                    return None;
+                }
+                code.clone()
+            }
+        };
+        Some(code)
+    }
+}
+
+/// Parses the declarations a script wrote to its `$RUNNER_SHELL` file.
+pub fn parse_declarations(content: &str) -> Result<Vec<Declaration>> {
+    let mut declarations = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = |message: &str| anyhow!("line {}: {}", index + 1, message);
+
+        let (kind, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        let declaration = match kind {
+            "env" | "alias" => {
+                let (name, value) = rest
+                    .split_once('=')
+                    .ok_or_else(|| invalid("expected NAME=value"))?;
+                let name = name.trim().to_string();
+                if name.is_empty() || name.contains(char::is_whitespace) {
+                    return Err(invalid("invalid name"));
+                }
+                if kind == "env" {
+                    Declaration::Env {
+                        name,
+                        value: value.to_string(),
+                    }
+                } else {
+                    Declaration::Alias {
+                        name,
+                        command: value.to_string(),
+                    }
+                }
+            }
+            "path" if !rest.is_empty() => Declaration::Path(rest.to_string()),
+            "line" => {
+                let (shell, code) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                let shell = Shell::from_name(shell)
+                    .ok_or_else(|| invalid("expected bash, zsh or fish after `line`"))?;
+                Declaration::Line {
+                    shell,
+                    code: code.trim().to_string(),
+                }
+            }
+            _ => return Err(invalid("expected env, path, alias or line")),
+        };
+        declarations.push(declaration);
+    }
+    Ok(declarations)
+}
+
+/// The `env` and `path` declarations as exports for the rest of the run.
+pub fn exports_of(declarations: &[Declaration]) -> Exports {
+    let mut exports = Exports::default();
+    for declaration in declarations {
+        match declaration {
+            Declaration::Env { name, value } => {
+                exports.variables.insert(name.clone(), value.clone());
+            }
+            Declaration::Path(dir) => {
+                let later = Exports {
+                    path: vec![PathBuf::from(dir)],
+                    ..Exports::default()
+                };
+                exports.merge(&later);
+            }
+            _ => {}
+        }
+    }
+    exports
+}
+
+/// The shell declarations of every script, kept in the state directory so
+/// the rc blocks and `runner shellenv` can be regenerated at any time.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ShellEnvStore {
+    #[serde(skip)]
+    path: PathBuf,
+    pub scripts: BTreeMap<String, Vec<Declaration>>,
+}
+
+impl ShellEnvStore {
+    pub fn load() -> Result<Self> {
+        let path = state_dir()?.join("shellenv.json");
+        let mut store = if path.exists() {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse {}", path.display()))?
+        } else {
+            ShellEnvStore::default()
+        };
+        store.path = path;
+        Ok(store)
+    }
+
+    /// Writes the store back to disk, replacing the previous file atomically.
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let temp_path = self.path.with_extension("json.tmp");
+        fs::write(&temp_path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", temp_path.display()))?;
+        fs::rename(&temp_path, &self.path)
+            .with_context(|| format!("Failed to replace {}", self.path.display()))
+    }
+
+    /// The directories scripts add to `PATH`.
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.scripts
+            .values()
+            .flatten()
+            .filter_map(|declaration| match declaration {
+                Declaration::Path(dir) => Some(PathBuf::from(dir)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Replaces the declarations of `script`, or forgets the script if it
+    /// declared nothing, and updates its block in the rc files.
+    pub fn apply(&mut self, script: &str, declarations: Vec<Declaration>) -> Result<()> {
+        if declarations.is_empty() {
+            if self.scripts.remove(script).is_none() {
+                return Ok(());
+            }
+        } else {
+            self.scripts.insert(script.to_string(), declarations);
+        }
+        self.save()?;
+
+        for shell in Shell::ALL {
+            let Some(rc_file) = shell.rc_file() else {
+                continue;
+            };
+            // Only touch the configuration of shells the user actually has.
+            if !rc_file.exists() && Shell::login() != Some(shell) {
+                continue;
+            }
+            let block = self
+                .scripts
+                .get(script)
+                .map(|declarations| render_block(shell, script, declarations));
+            if update_rc_file(&rc_file, script, block.as_deref())? {
+                info!(
+                    "Updated shell settings of {} in {}",
+                    script,
+                    rc_file.display()
+                );
+            } else {
+                debug!(
+                    "Shell settings of {} in {} are up to date",
+                    script,
+                    rc_file.display()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Every script's block for `shell`, to be evaluated by the shell.
+    pub fn render(&self, shell: Shell) -> String {
+        self.scripts
+            .iter()
+            .map(|(script, declarations)| render_block(shell, script, declarations))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn start_marker(script: &str) -> String {
+    format!("# >>> runner: {} >>>", script)
+}
+
+fn end_marker(script: &str) -> String {
+    format!("# <<< runner: {} <<<", script)
+}
+
+fn render_block(shell: Shell, script: &str, declarations: &[Declaration]) -> String {
+    let mut lines = vec![
+        start_marker(script),
+        String::from("# Managed by runner, changes are overwritten when the script runs again."),
+    ];
+    lines.extend(declarations.iter().filter_map(|d| d.render(shell)));
+    lines.push(end_marker(script));
+    lines.join("\n") + "\n"
+}
+
+/// Replaces the block of `script` in `rc_file` with `block`, appending it if
+/// the file has none, or removes it when `block` is `None`. Returns whether
+/// the file changed.
+fn update_rc_file(rc_file: &Path, script: &str, block: Option<&str>) -> Result<bool> {
+    let content = match fs::read_to_string(rc_file) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", rc_file.display())),
+    };
+
+    let (start, end) = (start_marker(script), end_marker(script));
+    let mut lines: Vec<&str> = content.lines().collect();
+    let existing = lines
+        .iter()
+        .position(|line| *line == start)
+        .and_then(|first| {
+            lines[first..]
+                .iter()
+                .position(|line| *line == end)
+                .map(|last| (first, first + last))
+        });
+
+    let replacement: Vec<&str> = block
+        .map(|block| block.lines().collect())
+        .unwrap_or_default();
+    match existing {
+        Some((first, last)) => {
+            lines.splice(first..=last, replacement);
+        }
+        None if block.is_some() => {
+            if lines.last().is_some_and(|line| !line.is_empty()) {
+                lines.push("");
+            }
+            lines.extend(replacement);
+        }
+        None => return Ok(false),
+    }
+
+    let mut updated = lines.join("\n");
+    if !updated.is_empty() {
+        updated.push('\n');
+    }
+    if updated == content {
+        return Ok(false);
+    }
+    if let Some(dir) = rc_file.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    fs::write(rc_file, updated)
+        .with_context(|| format!("Failed to write {}", rc_file.display()))?;
+    Ok(true)
+}
+
+/// Quotes `value` literally for bash and zsh.
+fn sh_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Quotes `value` literally for fish.
+fn fish_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\\', r"\\").replace('\'', r"\'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(shell: Shell, declarations: &str) -> String {
+        render_block(
+            shell,
+            "pyenv.sh",
+            &parse_declarations(declarations).unwrap(),
+        )
+    }
+
+    #[test]
+    fn renders_declarations_for_each_shell() {
+        let declarations =
+            "env PYENV_ROOT=/home/me/it's\npath /opt/bin\nline fish pyenv init - | source\n";
+        assert_eq!(
+            block(Shell::Bash, declarations),
+            "# >>> runner: pyenv.sh >>>\n\
+             # Managed by runner, changes are overwritten when the script runs again.\n\
+             export PYENV_ROOT='/home/me/it'\\''s'\n\
+             case \":$PATH:\" in *:'/opt/bin':*) ;; *) export PATH='/opt/bin':\"$PATH\" ;; esac\n\
+             # <<< runner: pyenv.sh <<<\n"
+        );
+        let fish = block(Shell::Fish, declarations);
+        assert!(fish.contains("set -gx PYENV_ROOT '/home/me/it\\'s'\n"));
+        assert!(fish.contains("\npyenv init - | source\n"));
+    }
+
+    #[test]
+    fn rejects_malformed_declarations() {
+        let error = |content| parse_declarations(content).unwrap_err().to_string();
+        assert_eq!(error("# comment\nenv NAME"), "line 2: expected NAME=value");
+        assert_eq!(error("path"), "line 1: expected env, path, alias or line");
+        assert_eq!(
+            error("line tcsh echo"),
+            "line 1: expected bash, zsh or fish after `line`"
+        );
+    }
+
+    #[test]
+    fn saves_the_store_without_leaving_a_temporary_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state").join("shellenv.json");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "{\"scripts\": {}}").unwrap();
+        let store = ShellEnvStore {
+            path: path.clone(),
+            scripts: BTreeMap::from([(
+                String::from("pyenv.sh"),
+                vec![Declaration::Path(String::from("/opt/bin"))],
+            )]),
+        };
+
+        store.save().unwrap();
+        let saved: ShellEnvStore =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.scripts, store.scripts);
+        assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn updates_rc_files_idempotently() {
+        let dir = tempfile::tempdir().unwrap();
+        let rc_file = dir.path().join("config").join(".bashrc");
+        let first = block(Shell::Bash, "env A=1\n");
+        let second = block(Shell::Bash, "env A=2\n");
+
+        assert!(update_rc_file(&rc_file, "pyenv.sh", Some(&first)).unwrap());
+        assert!(!update_rc_file(&rc_file, "pyenv.sh", Some(&first)).unwrap());
+        assert_eq!(fs::read_to_string(&rc_file).unwrap(), first);
+
+        let user_lines = "alias ll='ls -l'\n";
+        fs::write(&rc_file, format!("{}{}# after\n", first, user_lines)).unwrap();
+        assert!(update_rc_file(&rc_file, "pyenv.sh", Some(&second)).unwrap());
+        assert!(!update_rc_file(&rc_file, "pyenv.sh", Some(&second)).unwrap());
+        assert_eq!(
+            fs::read_to_string(&rc_file).unwrap(),
+            format!("{}{}# after\n", second, user_lines)
+        );
+
+        assert!(update_rc_file(&rc_file, "pyenv.sh", None).unwrap());
+        assert!(!update_rc_file(&rc_file, "pyenv.sh", None).unwrap());
+        assert_eq!(
+            fs::read_to_string(&rc_file).unwrap(),
+            format!("{}# after\n", user_lines)
+        );
+    }
+
+    #[test]
+    fn appends_blocks_after_existing_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let rc_file = dir.path().join(".zshrc");
+        fs::write(&rc_file, "export EDITOR=vim\n").unwrap();
+        let block = block(Shell::Zsh, "alias py=python3\n");
+
+        assert!(update_rc_file(&rc_file, "pyenv.sh", Some(&block)).unwrap());
+        assert!(!update_rc_file(&rc_file, "pyenv.sh", Some(&block)).unwrap());
+        assert_eq!(
+            fs::read_to_string(&rc_file).unwrap(),
+            format!("export EDITOR=vim\n\n{}", block)
+        );
+        assert!(!update_rc_file(&rc_file, "other.sh", None).unwrap());
+    }
+}
@@ -0,0 +1,244 @@
+use anyhow::{Context, Result};
+use jiff::Timestamp;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::scripts::action::Action;
+use crate::scripts::models::ScriptInfo;
+use crate::scripts::outcome::{ScriptOutcome, ScriptStatus};
+
+const STATE_FILE_NAME: &str = "state.json";
+
+/// A single recorded execution of a script on this machine.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionRecord {
+    pub script: String,
+    pub os_dir: String,
+    /// SHA-256 of the script contents at the time it ran, `None` if the
+    /// script could no longer be read when the run was recorded.
+    #[serde(default)]
+    pub content_hash: Option<String>,
+    pub started_at: Timestamp,
+    pub finished_at: Timestamp,
+    pub status: ScriptStatus,
+    pub exit_code: Option<i32>,
+    pub runner_version: String,
+    /// What the script was run for.
+    #[serde(default, skip_serializing_if = "Action::is_install")]
+    pub action: Action,
+}
+
+impl ExecutionRecord {
+    /// Whether this record belongs to `script`.
+    pub fn is_for(&self, script: &ScriptInfo) -> bool {
+        self.script == script.name && self.os_dir == script.os_type
+    }
+}
+
+/// History of script executions, persisted as JSON in the state directory.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StateStore {
+    #[serde(skip)]
+    path: PathBuf,
+    pub records: Vec<ExecutionRecord>,
+}
+
+impl StateStore {
+    /// Opens the store at the default location, see [`state_dir`].
+    pub fn open_default() -> Result<Self> {
+        Self::open(&state_dir()?.join(STATE_FILE_NAME))
+    }
+
+    /// Opens the store at `path`, starting empty if the file does not exist yet.
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut store = if path.exists() {
+            let content = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read state file: {}", path.display()))?;
+            serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse state file: {}", path.display()))?
+        } else {
+            debug!("No state file at {}, starting empty", path.display());
+            StateStore::default()
+        };
+        store.path = path.to_path_buf();
+        Ok(store)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes the store back to disk, replacing the previous file atomically.
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create state directory: {}", parent.display())
+            })?;
+        }
+        let temp_path = self.path.with_extension("json.tmp");
+        fs::write(&temp_path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write state file: {}", temp_path.display()))?;
+        fs::rename(&temp_path, &self.path)
+            .with_context(|| format!("Failed to replace state file: {}", self.path.display()))?;
+        Ok(())
+    }
+
+    /// Records the outcome of a script that was started. Skipped scripts and
+    /// scripts that were already satisfied are ignored.
+    ///
+    /// A script that can no longer be read, e.g. because it removed itself,
+    /// is recorded without a content hash.
+    pub fn record(&mut self, script: &ScriptInfo, outcome: &ScriptOutcome, action: Action) {
+        let Some(started_at) = outcome.started_at else {
+            return;
+        };
+        if matches!(
+            outcome.status,
+            ScriptStatus::Skipped | ScriptStatus::Satisfied
+        ) {
+            return;
+        }
+
+        let content_hash = match content_hash(&script.path) {
+            Ok(hash) => Some(hash),
+            Err(e) => {
+                warn!(
+                    "Recording {} without its content hash: {:#}",
+                    script.display_name(),
+                    e
+                );
+                None
+            }
+        };
+        self.records.push(ExecutionRecord {
+            script: script.name.clone(),
+            os_dir: script.os_type.clone(),
+            content_hash,
+            started_at,
+            finished_at: started_at
+                .checked_add(outcome.duration)
+                .unwrap_or(started_at),
+            status: outcome.status,
+            exit_code: outcome.exit_code,
+            runner_version: env!("CARGO_PKG_VERSION").to_string(),
+            action,
+        });
+    }
+
+    /// Most recent execution of `script`, if any.
+    pub fn last_run(&self, script: &ScriptInfo) -> Option<&ExecutionRecord> {
+        self.records
+            .iter()
+            .filter(|record| record.is_for(script))
+            .max_by_key(|record| record.started_at)
+    }
+
+    /// Whether `script` was applied successfully and not removed since.
+    pub fn is_installed(&self, script: &ScriptInfo) -> bool {
+        self.last_success(script)
+            .is_some_and(|record| record.action != Action::Remove)
+    }
+
+    /// Most recent successful execution of `script`, if any.
+    pub fn last_success(&self, script: &ScriptInfo) -> Option<&ExecutionRecord> {
+        self.records
+            .iter()
+            .filter(|record| record.is_for(script) && record.status == ScriptStatus::Ok)
+            .max_by_key(|record| record.started_at)
+    }
+}
+
+/// Directory holding the runner's persistent state.
+///
+/// Follows the XDG base directory spec (`$XDG_STATE_HOME/runner`, falling back
+/// to `~/.local/state/runner`), and `%LOCALAPPDATA%\runner` on Windows.
+pub fn state_dir() -> Result<PathBuf> {
+    if let Some(dir) = std::env::var_os("XDG_STATE_HOME").filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir).join("runner"));
+    }
+    if cfg!(windows) {
+        if let Some(dir) = std::env::var_os("LOCALAPPDATA") {
+            return Ok(PathBuf::from(dir).join("runner"));
+        }
+    }
+    let home = std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .context("Cannot determine the state directory: HOME is not set")?;
+    Ok(PathBuf::from(home)
+        .join(".local")
+        .join("state")
+        .join("runner"))
+}
+
+/// Hex encoded SHA-256 of the file at `path`.
+pub fn content_hash(path: &Path) -> Result<String> {
+    let content =
+        fs::read(path).with_context(|| format!("Failed to read script: {}", path.display()))?;
+    Ok(Sha256::digest(content)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn outcome(status: ScriptStatus) -> ScriptOutcome {
+        ScriptOutcome {
+            status,
+            exit_code: Some(0),
+            started_at: Some(Timestamp::UNIX_EPOCH),
+            ..ScriptOutcome::skipped(0, "")
+        }
+    }
+
+    #[test]
+    fn records_scripts_that_can_no_longer_be_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut script = ScriptInfo::for_test("unix", "gone.sh");
+        script.path = dir.path().join("gone.sh");
+        let mut store = StateStore::default();
+
+        store.record(&script, &outcome(ScriptStatus::Ok), Action::Install);
+        assert_eq!(store.records.len(), 1);
+        assert_eq!(store.records[0].content_hash, None);
+        assert!(store.is_installed(&script));
+
+        fs::write(&script.path, "echo hi\n").unwrap();
+        store.record(&script, &outcome(ScriptStatus::Failed), Action::Install);
+        assert_eq!(
+            store.records[1].content_hash.as_deref(),
+            Some(content_hash(&script.path).unwrap().as_str())
+        );
+    }
+
+    #[test]
+    fn ignores_scripts_that_did_not_run() {
+        let mut store = StateStore::default();
+        let script = ScriptInfo::for_test("unix", "a.sh");
+        store.record(
+            &script,
+            &ScriptOutcome::skipped(0, "skipped"),
+            Action::Install,
+        );
+        let satisfied = ScriptOutcome::satisfied(0, Timestamp::UNIX_EPOCH, Duration::ZERO, "");
+        store.record(&script, &satisfied, Action::Install);
+        assert!(store.records.is_empty());
+    }
+
+    #[test]
+    fn reads_records_written_with_a_content_hash() {
+        let record: ExecutionRecord = serde_json::from_str(
+            r#"{"script": "a.sh", "os_dir": "unix", "content_hash": "abc",
+                "started_at": "2024-01-01T00:00:00Z", "finished_at": "2024-01-01T00:00:01Z",
+                "status": "ok", "exit_code": 0, "runner_version": "0.1.0"}"#,
+        )
+        .unwrap();
+        assert_eq!(record.content_hash.as_deref(), Some("abc"));
+    }
+}
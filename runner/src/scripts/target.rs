@@ -0,0 +1,489 @@
+use anyhow::{anyhow, Result};
+use log::warn;
+use os_info::{Info, Type as OsType, Version};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// Architecture names accepted in script directories and file names, with
+/// the canonical (Debian style) name each one stands for.
+const ARCH_ALIASES: &[(&str, &str)] = &[
+    ("amd64", "amd64"),
+    ("x86_64", "amd64"),
+    ("x64", "amd64"),
+    ("arm64", "arm64"),
+    ("aarch64", "arm64"),
+    ("i386", "i386"),
+    ("i686", "i386"),
+    ("x86", "i386"),
+    ("armhf", "armhf"),
+    ("armv7", "armhf"),
+    ("armv7l", "armhf"),
+    ("arm", "armhf"),
+    ("riscv64", "riscv64"),
+    ("ppc64le", "ppc64le"),
+    ("s390x", "s390x"),
+];
+
+/// Built-in OS script directories for `os_info` types whose directory is not
+/// simply the lowercase type name.
+const OS_DIRS: &[(&str, &str)] = &[("macos", "darwin"), ("redhatenterprise", "rhel")];
+
+/// Built-in fallbacks from an OS script directory to the directory of the
+/// family it derives from. Every chain ends with `unix`, except on Windows.
+const OS_FALLBACKS: &[(&str, &str)] = &[
+    ("ubuntu", "debian"),
+    ("raspbian", "debian"),
+    ("kali", "debian"),
+    ("pop", "ubuntu"),
+    ("mint", "ubuntu"),
+    ("elementary", "ubuntu"),
+    ("zorin", "ubuntu"),
+    ("kdeneon", "ubuntu"),
+    ("fedora", "redhat"),
+    ("rhel", "redhat"),
+    ("centos", "redhat"),
+    ("rockylinux", "redhat"),
+    ("almalinux", "redhat"),
+    ("oraclelinux", "redhat"),
+    ("amazon", "redhat"),
+    ("nobara", "fedora"),
+    ("ultramarine", "fedora"),
+    ("bazzite", "fedora"),
+    ("bluefin", "fedora"),
+    ("manjaro", "arch"),
+    ("endeavouros", "arch"),
+    ("garuda", "arch"),
+    ("cachyos", "arch"),
+    ("artix", "arch"),
+    ("opensuse", "suse"),
+];
+
+/// Maps OS types to script directories and directories to the ones they
+/// fall back to. The configured entries take precedence over the built-in
+/// ones.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OsFamilies {
+    /// OS type (the lowercase `os_info` name, e.g. `pop` or `macos`) to
+    /// script directory.
+    pub dirs: BTreeMap<String, String>,
+    /// Script directory to the directory it falls back to.
+    pub fallbacks: BTreeMap<String, String>,
+}
+
+impl OsFamilies {
+    /// Script directory for `os_type`, or `None` if the type is unknown.
+    pub fn dir_for(&self, os_type: OsType) -> Option<String> {
+        if os_type == OsType::Unknown {
+            return None;
+        }
+        Some(self.resolve_dir(&os_type_key(os_type)))
+    }
+
+    /// Script directory for `name`, which is either an OS type key such as
+    /// `macos` or already a directory such as `darwin`.
+    pub fn resolve_dir(&self, name: &str) -> String {
+        let key = name.to_ascii_lowercase();
+        match self.dirs.get(&key) {
+            Some(dir) => dir.clone(),
+            None => OS_DIRS
+                .iter()
+                .find(|(os_type, _)| *os_type == key)
+                .map_or(name.to_string(), |(_, dir)| dir.to_string()),
+        }
+    }
+
+    /// The directories to collect scripts from for `os_dir`, most specific
+    /// first: `pop` gives `pop`, `ubuntu`, `debian`, `unix`.
+    pub fn chain(&self, os_dir: &str) -> Vec<String> {
+        let mut chain = vec![os_dir.to_string()];
+        while let Some(parent) = self.fallback(chain.last().unwrap()) {
+            if chain.contains(&parent) {
+                warn!(
+                    "OS fallback cycle: {} -> {}, ignoring the last step",
+                    chain.join(" -> "),
+                    parent
+                );
+                break;
+            }
+            chain.push(parent);
+        }
+        if !chain.iter().any(|dir| dir == "windows" || dir == "unix") {
+            chain.push(String::from("unix"));
+        }
+        chain
+    }
+
+    fn fallback(&self, os_dir: &str) -> Option<String> {
+        match self.fallbacks.get(os_dir) {
+            // An empty fallback ends the chain early.
+            Some(parent) => Some(parent.clone()).filter(|parent| !parent.is_empty()),
+            None => OS_FALLBACKS
+                .iter()
+                .find(|(dir, _)| *dir == os_dir)
+                .map(|(_, parent)| parent.to_string()),
+        }
+    }
+}
+
+/// The machine scripts are collected for: its OS script directories, OS
+/// version and CPU architecture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    /// OS script directories, most specific first, e.g. `ubuntu`, `debian`,
+    /// `unix`.
+    pub os_dirs: Vec<String>,
+    /// OS version, e.g. `12.0.0` on Debian 12 or `22.04` on Ubuntu.
+    pub version: Option<String>,
+    /// Canonical architecture name, e.g. `amd64` or `arm64`.
+    pub arch: String,
+}
+
+impl Target {
+    /// Describes the running machine. `os_override` replaces the OS script
+    /// directory derived from the detected OS type; either way the fallback
+    /// chain of `families` applies.
+    pub fn detect(info: &Info, os_override: Option<&str>, families: &OsFamilies) -> Self {
+        let os_dirs = match os_override
+            .map(str::to_string)
+            .or_else(|| families.dir_for(info.os_type()))
+        {
+            Some(os_dir) => families.chain(&os_dir),
+            None => {
+                warn!(
+                    "OS type {:?} not specifically supported, using only unix scripts",
+                    info.os_type()
+                );
+                vec![String::from("unix")]
+            }
+        };
+
+        let version = match info.version() {
+            Version::Semantic(major, minor, patch) => {
+                Some(format!("{}.{}.{}", major, minor, patch))
+            }
+            Version::Custom(version) => Some(version.clone()),
+            Version::Rolling(_) | Version::Unknown => None,
+        };
+
+        let arch = info
+            .architecture()
+            .and_then(canonical_arch)
+            .or_else(|| canonical_arch(std::env::consts::ARCH))
+            .unwrap_or(std::env::consts::ARCH)
+            .to_string();
+
+        Target {
+            os_dirs,
+            version,
+            arch,
+        }
+    }
+
+    /// The target as if this machine were `os` (an OS script directory or
+    /// type) and `arch`, for reviewing the scripts of another platform.
+    ///
+    /// The OS version is dropped when the OS changes, so version-specific
+    /// scripts are not picked for a machine whose version is unknown.
+    pub fn simulate(
+        &self,
+        os: Option<&str>,
+        arch: Option<&str>,
+        families: &OsFamilies,
+    ) -> Result<Self> {
+        let mut target = self.clone();
+        if let Some(os) = os {
+            let os_dirs = families.chain(&families.resolve_dir(os));
+            if os_dirs != self.os_dirs {
+                target.os_dirs = os_dirs;
+                target.version = None;
+            }
+        }
+        if let Some(arch) = arch {
+            target.arch = canonical_arch(arch)
+                .ok_or_else(|| {
+                    let mut known: Vec<&str> = ARCH_ALIASES
+                        .iter()
+                        .map(|(_, canonical)| *canonical)
+                        .collect();
+                    known.dedup();
+                    anyhow!(
+                        "Unknown architecture '{}' (expected one of {})",
+                        arch,
+                        known.join(", ")
+                    )
+                })?
+                .to_string();
+        }
+        Ok(target)
+    }
+
+    /// Checks whether a script at `relative_path` inside an OS directory is
+    /// meant for this target.
+    ///
+    /// Every directory between the OS directory and the script, and an
+    /// architecture suffix in the file name (`nodejs.arm64.sh`), must match
+    /// the target's version or architecture. Other directories are neutral.
+    pub fn match_variant(&self, relative_path: &Path) -> VariantMatch {
+        let mut qualifiers = Vec::new();
+        let Some(file_name) = relative_path.file_name().map(|n| n.to_string_lossy()) else {
+            return VariantMatch::Mismatch(String::from("not a file"));
+        };
+
+        if let Some(parent) = relative_path.parent() {
+            for component in parent.iter() {
+                qualifiers.extend(Qualifier::parse(&component.to_string_lossy()));
+            }
+        }
+
+        // `nodejs.arm64.sh` is the arm64 variant of `nodejs.sh`.
+        let mut name = file_name.to_string();
+        let parts: Vec<&str> = file_name.split('.').collect();
+        if parts.len() >= 3 {
+            let suffix = parts[parts.len() - 2];
+            if let Some(arch) = canonical_arch(suffix) {
+                qualifiers.push(Qualifier::Arch(arch.to_string()));
+                let mut logical = parts[..parts.len() - 2].to_vec();
+                logical.push(parts[parts.len() - 1]);
+                name = logical.join(".");
+            }
+        }
+
+        for qualifier in &qualifiers {
+            if !qualifier.matches(self) {
+                return VariantMatch::Mismatch(format!("only for {}", qualifier));
+            }
+        }
+        VariantMatch::Match {
+            name,
+            specificity: qualifiers.iter().map(Qualifier::specificity).sum(),
+            qualifiers,
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.os_dirs[0])?;
+        if let Some(version) = &self.version {
+            write!(f, " {}", version)?;
+        }
+        write!(f, " ({})", self.arch)
+    }
+}
+
+/// Outcome of checking a script path against a [`Target`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VariantMatch {
+    /// The script applies; `name` is its name without an architecture suffix.
+    Match {
+        name: String,
+        qualifiers: Vec<Qualifier>,
+        specificity: usize,
+    },
+    /// The script is for another version or architecture.
+    Mismatch(String),
+}
+
+/// A version or architecture restriction taken from a script's path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Qualifier {
+    Version(String),
+    Arch(String),
+}
+
+impl Qualifier {
+    /// Interprets a directory name: digits and dots are a version, known
+    /// architecture names an architecture, anything else no restriction.
+    fn parse(component: &str) -> Option<Self> {
+        if !component.is_empty()
+            && component
+                .split('.')
+                .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+        {
+            return Some(Qualifier::Version(component.to_string()));
+        }
+        canonical_arch(component).map(|arch| Qualifier::Arch(arch.to_string()))
+    }
+
+    fn matches(&self, target: &Target) -> bool {
+        match self {
+            Qualifier::Version(wanted) => target.version.as_deref().is_some_and(|version| {
+                version == wanted
+                    || version
+                        .strip_prefix(wanted.as_str())
+                        .is_some_and(|rest| rest.starts_with('.'))
+            }),
+            Qualifier::Arch(arch) => *arch == target.arch,
+        }
+    }
+
+    /// How much more specific this makes a script: `12.4` beats `12`, which
+    /// beats no version.
+    fn specificity(&self) -> usize {
+        match self {
+            Qualifier::Version(version) => version.split('.').count(),
+            Qualifier::Arch(_) => 1,
+        }
+    }
+}
+
+impl fmt::Display for Qualifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Qualifier::Version(version) => write!(f, "version {}", version),
+            Qualifier::Arch(arch) => write!(f, "arch {}", arch),
+        }
+    }
+}
+
+/// Canonical name of an architecture alias, e.g. `x86_64` -> `amd64`.
+pub fn canonical_arch(name: &str) -> Option<&'static str> {
+    let name = name.to_ascii_lowercase();
+    ARCH_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map(|(_, canonical)| *canonical)
+}
+
+/// Key of an OS type in [`OsFamilies::dirs`], e.g. `pop` or `redhatenterprise`.
+pub fn os_type_key(os_type: OsType) -> String {
+    format!("{:?}", os_type).to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(version: Option<&str>, arch: &str) -> Target {
+        Target {
+            os_dirs: vec![String::from("debian"), String::from("unix")],
+            version: version.map(String::from),
+            arch: arch.to_string(),
+        }
+    }
+
+    fn matched(variant: VariantMatch) -> (String, usize) {
+        match variant {
+            VariantMatch::Match {
+                name, specificity, ..
+            } => (name, specificity),
+            VariantMatch::Mismatch(reason) => panic!("unexpected mismatch: {}", reason),
+        }
+    }
+
+    #[test]
+    fn matches_version_directories_by_prefix() {
+        let target = target(Some("12.4.0"), "amd64");
+        assert_eq!(
+            matched(target.match_variant(Path::new("nodejs.sh"))),
+            (String::from("nodejs.sh"), 0)
+        );
+        assert_eq!(
+            matched(target.match_variant(Path::new("12/nodejs.sh"))),
+            (String::from("nodejs.sh"), 1)
+        );
+        assert_eq!(
+            matched(target.match_variant(Path::new("12.4/nodejs.sh"))),
+            (String::from("nodejs.sh"), 2)
+        );
+        assert_eq!(
+            target.match_variant(Path::new("1/nodejs.sh")),
+            VariantMatch::Mismatch(String::from("only for version 1"))
+        );
+        assert_eq!(
+            target.match_variant(Path::new("11/nodejs.sh")),
+            VariantMatch::Mismatch(String::from("only for version 11"))
+        );
+        // Other directories do not restrict the script.
+        assert_eq!(
+            matched(target.match_variant(Path::new("extras/nodejs.sh"))),
+            (String::from("nodejs.sh"), 0)
+        );
+    }
+
+    #[test]
+    fn never_matches_versions_when_the_version_is_unknown() {
+        assert!(matches!(
+            target(None, "amd64").match_variant(Path::new("12/nodejs.sh")),
+            VariantMatch::Mismatch(_)
+        ));
+    }
+
+    #[test]
+    fn matches_architecture_directories_and_suffixes() {
+        let target = target(Some("12.4.0"), "arm64");
+        assert_eq!(
+            matched(target.match_variant(Path::new("nodejs.aarch64.sh"))),
+            (String::from("nodejs.sh"), 1)
+        );
+        assert_eq!(
+            matched(target.match_variant(Path::new("12/arm64/nodejs.sh"))),
+            (String::from("nodejs.sh"), 2)
+        );
+        assert_eq!(
+            target.match_variant(Path::new("nodejs.x86_64.sh")),
+            VariantMatch::Mismatch(String::from("only for arch amd64"))
+        );
+        // Only the part before the extension can name an architecture.
+        assert_eq!(
+            matched(target.match_variant(Path::new("arm.sh"))),
+            (String::from("arm.sh"), 0)
+        );
+    }
+
+    fn families(dirs: &[(&str, &str)], fallbacks: &[(&str, &str)]) -> OsFamilies {
+        let map = |entries: &[(&str, &str)]| {
+            entries
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect()
+        };
+        OsFamilies {
+            dirs: map(dirs),
+            fallbacks: map(fallbacks),
+        }
+    }
+
+    #[test]
+    fn chains_builtin_families_down_to_unix() {
+        let families = OsFamilies::default();
+        assert_eq!(families.chain("pop"), ["pop", "ubuntu", "debian", "unix"]);
+        assert_eq!(
+            families.chain("nobara"),
+            ["nobara", "fedora", "redhat", "unix"]
+        );
+        assert_eq!(families.chain("darwin"), ["darwin", "unix"]);
+        assert_eq!(families.chain("windows"), ["windows"]);
+        assert_eq!(families.resolve_dir("macos"), "darwin");
+        assert_eq!(families.dir_for(OsType::Pop).as_deref(), Some("pop"));
+        assert_eq!(families.dir_for(OsType::Unknown), None);
+    }
+
+    #[test]
+    fn chains_amazon_linux_to_the_redhat_family() {
+        let families = OsFamilies::default();
+        let dir = families.dir_for(OsType::Amazon).unwrap();
+        assert_eq!(families.chain(&dir), ["amazon", "redhat", "unix"]);
+    }
+
+    #[test]
+    fn prefers_configured_families() {
+        let families = families(
+            &[("macos", "mac")],
+            &[("mac", "darwin"), ("pop", "debian"), ("ubuntu", "")],
+        );
+        assert_eq!(families.resolve_dir("macos"), "mac");
+        assert_eq!(families.chain("mac"), ["mac", "darwin", "unix"]);
+        assert_eq!(families.chain("pop"), ["pop", "debian", "unix"]);
+        // An empty fallback ends the chain, which still ends with unix.
+        assert_eq!(families.chain("mint"), ["mint", "ubuntu", "unix"]);
+    }
+
+    #[test]
+    fn stops_at_fallback_cycles() {
+        let families = families(&[], &[("a", "b"), ("b", "a")]);
+        assert_eq!(families.chain("a"), ["a", "b", "unix"]);
+    }
+}
@@ -1,4 +1,5 @@
 #!/bin/sh
+# @lock: apk
 
 # Update system packages
 apk update && apk upgrade
@@ -1,4 +1,5 @@
 #!/bin/sh
+# @lock: apk
 
 # Install required packages
 apk add git openssh-client pcsc-lite ccid yubikey-manager 
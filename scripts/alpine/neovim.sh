@@ -1,4 +1,6 @@
 #!/bin/sh
+# @lock: apk
+# @interactive
 
 # Alpine has neovim in its repositories
 apk add neovim python3 py3-pip fd
@@ -1,4 +1,5 @@
 #!/bin/sh
+# @lock: apk
 
 # Install curl
 apk add curl
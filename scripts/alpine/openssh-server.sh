@@ -1,4 +1,6 @@
 #!/bin/sh
+# @lock: apk
+# @interactive
 
 set -e
 
@@ -1,4 +1,5 @@
 #!/bin/sh
+# @lock: apk
 
 # Install Podman
 apk add podman skopeo buildah
@@ -1,4 +1,5 @@
 #!/bin/sh
+# @lock: apk
 
 # Install Python and dependencies
 apk add python3 py3-pip readline-dev
@@ -0,0 +1,10 @@
+#!/bin/bash
+# @description: Update the system and install base packages and common tools
+# @tags: base
+# @needs-root
+# @lock: pacman
+
+sudo pacman -Syu --noconfirm
+sudo pacman -S --needed --noconfirm htop curl git python python-pip base-devel cmake clang llvm \
+  pam-u2f screen tmux vim go remmina flatpak
+flatpak remote-add --user --if-not-exists flathub https://flathub.org/repo/flathub.flatpakrepo
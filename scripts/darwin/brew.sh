@@ -1,4 +1,5 @@
 #!/bin/bash
+# @lock: brew
 
 # Check if Homebrew is installed, install if not
 if ! command -v brew &> /dev/null; then
@@ -1,4 +1,5 @@
 #!/bin/bash
+# @lock: brew
 
 set -e
 
@@ -8,23 +9,23 @@ brew install --cask chromium
 
 echo "Setting CHROME_EXECUTABLE variable"
 export CHROME_EXECUTABLE="/Applications/Chromium.app/Contents/MacOS/Chromium"
-echo "CHROME_EXECUTABLE=$CHROME_EXECUTABLE" | tee -a ~/.zshrc
 
 # Install Flutter Version Manager (FVM)
 curl -fsSL https://raw.githubusercontent.com/leoafarias/fvm/refs/heads/main/scripts/install.sh | bash
 fvm install stable
-if ! grep -q "alias flutter=" ~/.zshrc; then
-    printf '%s\n' \
-        'export PATH="$HOME/.fvm/bin:$PATH"' \
-        'alias flutter="fvm flutter"' >> ~/.zshrc
-fi
 
 # Install Android SDK and Java
 brew install openjdk@21
 sudo ln -sfn $(brew --prefix)/opt/openjdk@21/libexec/openjdk.jdk /Library/Java/JavaVirtualMachines/openjdk-21.jdk
 export JAVA_HOME=$(/usr/libexec/java_home -v 21)
-echo "export JAVA_HOME=$JAVA_HOME" | tee -a ~/.zshrc
-echo "export PATH=\$PATH:\$JAVA_HOME/bin" | tee -a ~/.zshrc
+if [[ -n $RUNNER_SHELL ]]; then
+    printf '%s\n' \
+        "env CHROME_EXECUTABLE=$CHROME_EXECUTABLE" \
+        "path $HOME/.fvm/bin" \
+        "alias flutter=fvm flutter" \
+        "env JAVA_HOME=$JAVA_HOME" \
+        "path $JAVA_HOME/bin" >> "$RUNNER_SHELL"
+fi
 java -version
 brew install android-platform-tools
 brew install --cask android-studio
@@ -37,4 +38,4 @@ yes | fvm flutter doctor --android-licenses
 fvm flutter doctor -v
 
 echo "Flutter installation completed for macOS"
-echo "You may need to restart your terminal or run 'source ~/.zshrc' to apply all changes"
+echo "You may need to restart your terminal or run 'eval \"\$(runner shellenv)\"' to apply all changes"
@@ -1,4 +1,5 @@
 #!/bin/bash
+# @lock: brew
 
 # Install required packages via Homebrew
 brew install yubikey-agent pinentry-mac
@@ -6,8 +7,7 @@ brew install --cask yubico-yubikey-manager
 
 # Set up Yubikey agent
 export SSH_AUTH_SOCK="$HOME/Library/Containers/com.filippo.yubikey-agent/Data/yubikey-agent.sock"
-echo "export SSH_AUTH_SOCK=\"$HOME/Library/Containers/com.filippo.yubikey-agent/Data/yubikey-agent.sock\"" | tee -a ~/.zshrc
-echo "export SSH_AUTH_SOCK=\"$HOME/Library/Containers/com.filippo.yubikey-agent/Data/yubikey-agent.sock\"" | tee -a ~/.bashrc
+[[ -n $RUNNER_SHELL ]] && echo "env SSH_AUTH_SOCK=$SSH_AUTH_SOCK" >> "$RUNNER_SHELL"
 
 # Create LaunchAgent for yubikey-agent
 mkdir -p ~/Library/LaunchAgents
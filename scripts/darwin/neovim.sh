@@ -1,4 +1,6 @@
 #!/bin/bash
+# @lock: brew
+# @provides: nvim>=0.10
 
 brew install neovim
 brew install python@3 fd pyenv
@@ -1,4 +1,5 @@
 #!/bin/bash
+# @lock: brew
 
 brew install node@lts
 
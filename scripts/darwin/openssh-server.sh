@@ -1,4 +1,6 @@
 #!/bin/bash
+# @lock: brew
+# @interactive
 
 set -e
 
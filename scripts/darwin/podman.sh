@@ -1,4 +1,6 @@
 #!/bin/bash
+# @lock: brew
+# @provides: podman
 
 # Install podman using Homebrew
 brew install podman podman-compose
@@ -1,4 +1,5 @@
 #!/bin/bash
+# @lock: brew
 
 # Install Python and related tools via Homebrew
 brew install python python-pip pipx readline
@@ -10,17 +11,22 @@ pipx install poetry
 # Install pyenv using Homebrew
 brew install pyenv
 
-# Add pyenv to shell configuration
-echo 'export PYENV_ROOT="$HOME/.pyenv"' >> ~/.zshrc
-echo '[[ -d $PYENV_ROOT/bin ]] && export PATH="$PYENV_ROOT/bin:$PATH"' >> ~/.zshrc
-echo 'eval "$(pyenv init --path)"' >> ~/.zshrc
-echo 'eval "$(pyenv init -)"' >> ~/.zshrc
-
 # Set up pyenv in current shell
 export PYENV_ROOT="$HOME/.pyenv"
 [[ -d $PYENV_ROOT/bin ]] && export PATH="$PYENV_ROOT/bin:$PATH"
 eval "$(pyenv init -)"
 
+# Add pyenv to shell configuration
+if [[ -n $RUNNER_SHELL ]]; then
+    cat >> "$RUNNER_SHELL" <<EOF
+env PYENV_ROOT=$PYENV_ROOT
+path $PYENV_ROOT/bin
+line bash eval "\$(pyenv init - bash)"
+line zsh eval "\$(pyenv init - zsh)"
+line fish pyenv init - fish | source
+EOF
+fi
+
 # Install required dependencies for building Python
 brew install openssl readline sqlite3 xz zlib tcl-tk
 
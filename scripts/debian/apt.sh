@@ -1,4 +1,8 @@
 #!/bin/bash
+# @description: Update the system and install base packages, flatpak and common tools
+# @tags: base
+# @needs-root
+# @lock: apt, snap, flatpak
 
 sudo apt update && sudo apt upgrade -y && sudo snap refresh
 sudo apt install -y flatpak && flatpak update -y
@@ -1,4 +1,5 @@
 #!/bin/bash
+# @lock: apt
 
 sudo apt-get update
 sudo apt install  -y gnome-terminal
@@ -1,3 +1,4 @@
+# @lock: apt
 # Add Docker's official GPG key:
 sudo apt-get update
 sudo apt-get install ca-certificates curl
@@ -1,4 +1,5 @@
 #!/bin/bash
+# @lock: apt
 
 sudo apt update && sudo apt upgrade -y && sudo apt install -y flatpak
 
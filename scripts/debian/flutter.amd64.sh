@@ -1,4 +1,10 @@
 #!/bin/bash
+# @description: Install Flutter build dependencies, Chromium and fvm
+# @tags: dev, mobile
+# @requires: apt.sh, snap.sh
+# @needs-root
+# @lock: apt, snap
+# @provides: fvm, chromium
 
 set -e
 
@@ -10,22 +16,23 @@ sudo apt install -y curl git unzip xz-utils zip libglu1-mesa \
 echo "Installing chromium and setting CHROME_EXECUTABLE variable"
 sudo snap install chromium
 export CHROME_EXECUTABLE=/snap/bin/chromium
-echo "CHROME_EXECUTABLE=$CHROME_EXECUTABLE" | tee -a ~/.bashrc
     
 curl -fsSL https://raw.githubusercontent.com/leoafarias/fvm/refs/heads/main/scripts/install.sh | bash
 
 fvm install 2.8.1 # used for legacy KW mobile, soon to be removed
 fvm install stable
 
-if ! grep -q "alias flutter=" ~/.bashrc; then
-    printf '%s\n' \
-        'export PATH="$HOME/.fvm/bin:$PATH"' \
-        'alias flutter="fvm flutter"' >> ~/.bashrc
-fi
-
 sudo apt install -y openjdk-21-jdk openjdk-21-jre android-tools-adb android-tools-fastboot
 export JAVA_HOME=/usr/lib/jvm/java-21-openjdk-amd64
 export PATH=$PATH:$JAVA_HOME/bin
+if [[ -n $RUNNER_SHELL ]]; then
+    printf '%s\n' \
+        "env CHROME_EXECUTABLE=$CHROME_EXECUTABLE" \
+        "path $HOME/.fvm/bin" \
+        "alias flutter=fvm flutter" \
+        "env JAVA_HOME=$JAVA_HOME" \
+        "path $JAVA_HOME/bin" >> "$RUNNER_SHELL"
+fi
 java -version
 
 # Android Studio
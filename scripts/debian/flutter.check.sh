@@ -0,0 +1,7 @@
+#!/bin/bash
+# Flutter is set up once fvm has the stable SDK and Android Studio is unpacked
+
+command -v fvm > /dev/null || exit 1
+fvm list 2> /dev/null | grep -q stable || exit 1
+[[ -x /opt/android-studio/bin/studio.sh ]] || exit 1
+[[ -d /usr/lib/jvm/java-21-openjdk-amd64 ]]
@@ -1,6 +1,8 @@
+# @lock: apt
+# @provides: yubikey-agent
 sudo apt install -y libpam-u2f yubikey-agent pinentry-qt pcscd
 export SSH_AUTH_SOCK="${XDG_RUNTIME_DIR}/yubikey-agent/yubikey-agent.sock"
-echo "export SSH_AUTH_SOCK=\"${XDG_RUNTIME_DIR}/yubikey-agent/yubikey-agent.sock\"" | tee -a ~/.bashrc
+[[ -n $RUNNER_SHELL ]] && echo "env SSH_AUTH_SOCK=$SSH_AUTH_SOCK" >> "$RUNNER_SHELL"
 
 echo "[Unit]
 Description=Seamless ssh-agent for YubiKeys
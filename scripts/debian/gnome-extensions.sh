@@ -1,4 +1,6 @@
 #!/bin/bash
+# @lock: apt
+# @skip-in: container, wsl
 
 # Script to install common Gnome extensions
 
@@ -0,0 +1,4 @@
+#!/bin/bash
+# The configuration in ~/.config/nvim is kept
+
+brew uninstall neovim
@@ -1,3 +1,13 @@
+# @lock: apt, brew
+# @actions: upgrade
+# @provides: nvim>=0.10
+
+if [ "$RUNNER_ACTION" = upgrade ]; then
+    brew upgrade neovim
+    git -C ~/.config/nvim pull --ff-only
+    exit 0
+fi
+
 /bin/bash -c "$(curl -fsSL https://raw.githubusercontent.com/Homebrew/install/HEAD/install.sh)"
 brew install neovim
 sudo apt-get update
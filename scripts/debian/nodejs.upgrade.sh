@@ -0,0 +1,9 @@
+#!/bin/bash
+# Move to the latest LTS release, keeping the globally installed packages
+
+export NVM_DIR="$HOME/.nvm"
+[ -s "$NVM_DIR/nvm.sh" ] && \. "$NVM_DIR/nvm.sh"
+
+nvm install --lts --reinstall-packages-from=current
+nvm alias default 'lts/*'
+npm install -g npm@latest
@@ -1,4 +1,6 @@
 #!/bin/bash
+# @lock: apt
+# @interactive
 
 set -e
 
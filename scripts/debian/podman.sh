@@ -1,4 +1,14 @@
 #!/bin/bash
+# @lock: apt, flatpak
+# @packages: podman, io.podman_desktop.PodmanDesktop
+# @actions: remove
+# @provides: podman
+
+if [ "$RUNNER_ACTION" = remove ]; then
+    flatpak uninstall -y io.podman_desktop.PodmanDesktop
+    sudo apt -y remove podman
+    exit 0
+fi
 
 sudo apt -y install podman
 
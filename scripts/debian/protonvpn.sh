@@ -1,4 +1,5 @@
 #!/bin/bash
+# @lock: apt
 
 wget https://repo.protonvpn.com/debian/dists/stable/main/binary-all/protonvpn-stable-release_1.0.6_all.deb
 sudo dpkg -i ./protonvpn-stable-release_1.0.6_all.deb && sudo apt update
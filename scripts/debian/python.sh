@@ -1,4 +1,8 @@
 #!/bin/bash
+# @lock: apt
+# @packages: python3, python3-pip, python3-venv, pipx, poetry, pyenv
+# @check: "$HOME/.pyenv/bin/pyenv" versions --bare 2>/dev/null | grep -q '^3\.11\.'
+# @provides: pyenv, pipx, poetry, uv
 
 sudo apt install -y python3 python3-pip python3-venv pipx libreadline8 libreadline-dev
 pipx ensurepath
@@ -6,14 +10,22 @@ pipx install poetry
 
 curl https://pyenv.run | bash
 
-echo 'export PYENV_ROOT="$HOME/.pyenv"' >> ~/.bashrc
-echo '[[ -d $PYENV_ROOT/bin ]] && export PATH="$PYENV_ROOT/bin:$PATH"' >> ~/.bashrc
-echo 'eval "$(pyenv init - bash)"' >> ~/.bashrc
-
 export PYENV_ROOT="$HOME/.pyenv"
 [[ -d $PYENV_ROOT/bin ]] && export PATH="$PYENV_ROOT/bin:$PATH"
 eval "$(pyenv init - bash)"
 
+# Set up pyenv in the shell configuration and for later scripts of the run
+if [[ -n $RUNNER_SHELL ]]; then
+    cat >> "$RUNNER_SHELL" <<EOF
+env PYENV_ROOT=$PYENV_ROOT
+path $PYENV_ROOT/bin
+line bash eval "\$(pyenv init - bash)"
+line zsh eval "\$(pyenv init - zsh)"
+line fish pyenv init - fish | source
+EOF
+    echo "$PYENV_ROOT/shims" >> "$RUNNER_PATH"
+fi
+
 # Install build dependencies before installing version
 sudo apt update; sudo apt install -y build-essential libssl-dev zlib1g-dev \
     libbz2-dev libreadline-dev libsqlite3-dev curl git \
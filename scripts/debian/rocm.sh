@@ -1,4 +1,5 @@
 #!/bin/bash
+# @lock: apt
 
 sudo apt install -y "linux-headers-$(uname -r)" "linux-modules-extra-$(uname -r)"
 sudo apt install -y python2-setuptools python3-wheel
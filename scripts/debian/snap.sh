@@ -1,4 +1,9 @@
 #!/bin/bash
+# @description: Install desktop applications from the Snap store
+# @tags: desktop
+# @needs-root
+# @lock: snap
+# @skip-in: container, wsl
 
 sudo snap install bitwarden
 sudo snap install obsidian --classic
@@ -0,0 +1,10 @@
+#!/bin/bash
+# @description: Update the system and install base packages and common tools
+# @tags: base
+# @needs-root
+# @lock: dnf
+
+sudo dnf upgrade -y
+sudo dnf install -y htop curl git python3 python3-pip gcc gcc-c++ cmake clang llvm \
+  pam-u2f screen tmux vim-enhanced golang remmina flatpak
+flatpak remote-add --user --if-not-exists flathub https://flathub.org/repo/flathub.flatpakrepo
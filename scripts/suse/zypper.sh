@@ -0,0 +1,11 @@
+#!/bin/bash
+# @description: Update the system and install base packages and common tools
+# @tags: base
+# @needs-root
+# @lock: zypper
+
+sudo zypper --non-interactive refresh
+sudo zypper --non-interactive update
+sudo zypper --non-interactive install htop curl git python3 python3-pip gcc gcc-c++ cmake clang llvm \
+  pam_u2f screen tmux vim go remmina flatpak
+flatpak remote-add --user --if-not-exists flathub https://flathub.org/repo/flathub.flatpakrepo
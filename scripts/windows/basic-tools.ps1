@@ -1,4 +1,5 @@
 #!/usr/bin/env pwsh
+# @lock: choco
 
 # Install Chocolatey if not installed
 if (!(Get-Command choco -ErrorAction SilentlyContinue)) {
@@ -1,4 +1,5 @@
 #!/usr/bin/env pwsh
+# @lock: choco
 
 # Install required dependencies
 choco install -y git unzip curl
@@ -1,4 +1,6 @@
 #!/usr/bin/env pwsh
+# @lock: choco
+# @interactive
 
 # Install Git and YubiKey tools using Chocolatey
 choco install -y git yubikey-manager openssh
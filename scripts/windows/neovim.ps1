@@ -1,4 +1,5 @@
 #!/usr/bin/env pwsh
+# @lock: choco
 
 # Install Neovim and dependencies using chocolatey
 choco install -y neovim fd ripgrep
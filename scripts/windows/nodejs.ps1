@@ -1,4 +1,5 @@
 #!/usr/bin/env pwsh
+# @lock: choco
 
 # Install NVM for Windows using Chocolatey
 choco install -y nvm.portable
@@ -1,4 +1,5 @@
 #!/usr/bin/env pwsh
+# @interactive
 
 # Install OpenSSH Server and Client features
 Add-WindowsCapability -Online -Name OpenSSH.Client~~~~0.0.1.0
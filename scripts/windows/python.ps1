@@ -1,4 +1,5 @@
 #!/usr/bin/env pwsh
+# @lock: choco
 
 # Check if chocolatey is installed, install if not
 if (!(Get-Command choco -ErrorAction SilentlyContinue)) {